@@ -133,6 +133,38 @@ pub struct Values {
 	pub string_values: FxHashMap<String, String>,
 }
 
+/// A value was found under a key, but with a different type than requested.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TypeMismatch {
+	/// Name of the type the value was actually recorded as
+	pub found: &'static str,
+}
+
+/// The type a value is recorded as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+	/// A `bool` value
+	Bool,
+	/// An `i64` value
+	I64,
+	/// A `u64` value
+	U64,
+	/// A `String` value
+	String,
+}
+
+impl ValueKind {
+	/// Name of the type, as returned in a `TypeMismatch`
+	fn name(&self) -> &'static str {
+		match self {
+			ValueKind::Bool => "bool",
+			ValueKind::I64 => "i64",
+			ValueKind::U64 => "u64",
+			ValueKind::String => "String",
+		}
+	}
+}
+
 impl Values {
 	/// Returns a new instance of Values
 	pub fn new() -> Self {
@@ -146,6 +178,124 @@ impl Values {
 			self.u64_values.is_empty() &&
 			self.string_values.is_empty()
 	}
+
+	/// Extends the `bool` values with the contents of an iterator
+	pub fn extend_bools(&mut self, it: impl IntoIterator<Item = (String, bool)>) {
+		self.bool_values.extend(it);
+	}
+
+	/// Extends the `i64` values with the contents of an iterator
+	pub fn extend_i64s(&mut self, it: impl IntoIterator<Item = (String, i64)>) {
+		self.i64_values.extend(it);
+	}
+
+	/// Extends the `u64` values with the contents of an iterator
+	pub fn extend_u64s(&mut self, it: impl IntoIterator<Item = (String, u64)>) {
+		self.u64_values.extend(it);
+	}
+
+	/// Extends the `String` values with the contents of an iterator
+	pub fn extend_strings(&mut self, it: impl IntoIterator<Item = (String, String)>) {
+		self.string_values.extend(it);
+	}
+
+	/// Removes `key` from whichever collection holds it, returning whether anything was removed
+	pub fn remove(&mut self, key: &str) -> bool {
+		// Avoid short-circuiting, a key could have been recorded with more than one type.
+		self.bool_values.remove(key).is_some() |
+			self.i64_values.remove(key).is_some() |
+			self.u64_values.remove(key).is_some() |
+			self.string_values.remove(key).is_some()
+	}
+
+	/// Retains only the values whose key satisfies `pred`, across all collections
+	pub fn retain(&mut self, pred: impl Fn(&str) -> bool) {
+		self.bool_values.retain(|k, _| pred(k));
+		self.i64_values.retain(|k, _| pred(k));
+		self.u64_values.retain(|k, _| pred(k));
+		self.string_values.retain(|k, _| pred(k));
+	}
+
+	/// Returns the `bool` value for `key`, or an error if it was recorded with another type
+	pub fn get_bool_strict(&self, key: &str) -> Result<Option<bool>, TypeMismatch> {
+		self.get_strict(key, self.bool_values.get(key).copied())
+	}
+
+	/// Returns the `i64` value for `key`, or an error if it was recorded with another type
+	pub fn get_i64_strict(&self, key: &str) -> Result<Option<i64>, TypeMismatch> {
+		self.get_strict(key, self.i64_values.get(key).copied())
+	}
+
+	/// Returns the `u64` value for `key`, or an error if it was recorded with another type
+	pub fn get_u64_strict(&self, key: &str) -> Result<Option<u64>, TypeMismatch> {
+		self.get_strict(key, self.u64_values.get(key).copied())
+	}
+
+	/// Returns the `String` value for `key`, or an error if it was recorded with another type
+	pub fn get_string_strict(&self, key: &str) -> Result<Option<&str>, TypeMismatch> {
+		self.get_strict(key, self.string_values.get(key).map(|s| s.as_str()))
+	}
+
+	/// Describes every value as a `key: type = value` line, sorted by key
+	pub fn describe(&self) -> Vec<String> {
+		let bool_iter =
+			self.bool_values.iter().map(|(k, v)| (k, ValueKind::Bool.name(), v.to_string()));
+		let i64_iter =
+			self.i64_values.iter().map(|(k, v)| (k, ValueKind::I64.name(), v.to_string()));
+		let u64_iter =
+			self.u64_values.iter().map(|(k, v)| (k, ValueKind::U64.name(), v.to_string()));
+		let string_iter = self
+			.string_values
+			.iter()
+			.map(|(k, v)| (k, ValueKind::String.name(), format!("\"{}\"", v)));
+		let mut entries =
+			bool_iter.chain(i64_iter).chain(u64_iter).chain(string_iter).collect::<Vec<_>>();
+		entries.sort();
+		entries.into_iter().map(|(k, ty, v)| format!("{}: {} = {}", k, ty, v)).collect()
+	}
+
+	/// Checks that every key in `schema` is recorded with the expected type, returning a
+	/// description of each violation otherwise. Keys not in `schema` are ignored.
+	pub fn conforms_to(&self, schema: &[(&str, ValueKind)]) -> Result<(), Vec<String>> {
+		let violations = schema
+			.iter()
+			.filter(|(key, kind)| !self.contains_key_of(key, *kind))
+			.map(|(key, kind)| match self.type_of(key) {
+				Some(found) => format!("`{}` is {}, expected {}", key, found.name(), kind.name()),
+				None => format!("`{}` is missing", key),
+			})
+			.collect::<Vec<_>>();
+		if violations.is_empty() {
+			Ok(())
+		} else {
+			Err(violations)
+		}
+	}
+
+	fn get_strict<T>(&self, key: &str, value: Option<T>) -> Result<Option<T>, TypeMismatch> {
+		match (value, self.type_of(key)) {
+			(Some(value), _) => Ok(Some(value)),
+			(None, Some(found)) => Err(TypeMismatch { found: found.name() }),
+			(None, None) => Ok(None),
+		}
+	}
+
+	/// Checks if `key` was recorded as a value of `kind`
+	fn contains_key_of(&self, key: &str, kind: ValueKind) -> bool {
+		match kind {
+			ValueKind::Bool => self.bool_values.contains_key(key),
+			ValueKind::I64 => self.i64_values.contains_key(key),
+			ValueKind::U64 => self.u64_values.contains_key(key),
+			ValueKind::String => self.string_values.contains_key(key),
+		}
+	}
+
+	/// The type `key` was recorded as, if any
+	fn type_of(&self, key: &str) -> Option<ValueKind> {
+		[ValueKind::Bool, ValueKind::I64, ValueKind::U64, ValueKind::String]
+			.into_iter()
+			.find(|kind| self.contains_key_of(key, *kind))
+	}
 }
 
 impl Visit for Values {
@@ -443,6 +593,7 @@ impl From<TraceEvent> for sp_rpc::tracing::Event {
 			target: trace_event.target,
 			data,
 			parent_id: trace_event.parent_id.map(|id| id.into_u64()),
+			level: Some(trace_event.level.to_string()),
 		}
 	}
 }
@@ -565,6 +716,94 @@ mod tests {
 		assert_eq!(values.string_values.get("test_str").unwrap(), &test_str.to_owned());
 	}
 
+	#[test]
+	fn test_values_extend() {
+		let mut values = Values::new();
+		values.extend_bools(vec![("a".to_owned(), true), ("b".to_owned(), false)]);
+		values.extend_i64s(vec![("c".to_owned(), -1)]);
+		values.extend_u64s(vec![("d".to_owned(), 1), ("e".to_owned(), 2), ("f".to_owned(), 3)]);
+		values.extend_strings(vec![("g".to_owned(), "h".to_owned())]);
+		assert_eq!(values.bool_values.len(), 2);
+		assert_eq!(values.i64_values.len(), 1);
+		assert_eq!(values.u64_values.len(), 3);
+		assert_eq!(values.string_values.len(), 1);
+		assert_eq!(values.u64_values.get("e").unwrap(), &2);
+	}
+
+	#[test]
+	fn test_values_strict_getters() {
+		let mut values = Values::new();
+		values.u64_values.insert("amount".to_owned(), 1000);
+		values.string_values.insert("who".to_owned(), "alice".to_owned());
+
+		assert_eq!(values.get_u64_strict("amount"), Ok(Some(1000)));
+		assert_eq!(values.get_u64_strict("missing"), Ok(None));
+		assert_eq!(values.get_u64_strict("who"), Err(TypeMismatch { found: "String" }));
+		assert_eq!(values.get_string_strict("who"), Ok(Some("alice")));
+		assert_eq!(values.get_string_strict("amount"), Err(TypeMismatch { found: "u64" }));
+		assert_eq!(values.get_bool_strict("amount"), Err(TypeMismatch { found: "u64" }));
+		assert_eq!(values.get_i64_strict("missing"), Ok(None));
+	}
+
+	#[test]
+	fn test_values_describe() {
+		let mut values = Values::new();
+		values.extend_u64s([("amount".to_owned(), 1000)]);
+		values.extend_i64s([("delta".to_owned(), -5)]);
+		values.extend_bools([("success".to_owned(), true)]);
+		values.extend_strings([("call".to_owned(), "transfer".to_owned())]);
+		assert_eq!(
+			values.describe(),
+			vec![
+				"amount: u64 = 1000",
+				"call: String = \"transfer\"",
+				"delta: i64 = -5",
+				"success: bool = true",
+			]
+		);
+		assert!(Values::new().describe().is_empty());
+	}
+
+	#[test]
+	fn test_values_remove_and_retain() {
+		let mut values = Values::new();
+		values.extend_bools([("success".to_owned(), true)]);
+		values.extend_i64s([("delta".to_owned(), -5)]);
+		values.extend_u64s([("amount".to_owned(), 1000), ("fee".to_owned(), 1)]);
+		values.extend_strings([("call".to_owned(), "transfer".to_owned())]);
+
+		assert!(values.remove("delta"));
+		assert!(!values.remove("delta"));
+		assert!(values.i64_values.is_empty());
+
+		values.retain(|key| key != "fee" && key != "call");
+		assert_eq!(values.describe(), vec!["amount: u64 = 1000", "success: bool = true"]);
+	}
+
+	#[test]
+	fn test_values_conforms_to() {
+		let mut values = Values::new();
+		values.extend_u64s([("amount".to_owned(), 1000)]);
+		values.extend_strings([("call".to_owned(), "transfer".to_owned())]);
+		values.extend_bools([("extra".to_owned(), true)]);
+
+		assert_eq!(
+			values.conforms_to(&[("amount", ValueKind::U64), ("call", ValueKind::String)]),
+			Ok(())
+		);
+		assert_eq!(
+			values.conforms_to(&[("amount", ValueKind::U64), ("fee", ValueKind::U64)]),
+			Err(vec!["`fee` is missing".to_owned()])
+		);
+		assert_eq!(
+			values.conforms_to(&[("amount", ValueKind::I64), ("call", ValueKind::Bool)]),
+			Err(vec![
+				"`amount` is u64, expected i64".to_owned(),
+				"`call` is String, expected bool".to_owned()
+			])
+		);
+	}
+
 	#[test]
 	fn test_event() {
 		let (sub, _spans, events) = setup_subscriber();
@@ -57,6 +57,23 @@ fn bench_multiplication(c: &mut Criterion) {
 	});
 }
 
+fn bench_karatsuba(c: &mut Criterion) {
+	let mut group = c.benchmark_group("karatsuba");
+
+	for size in [16, 32, 40, 48, 64, 128].iter() {
+		let a = random_big_uint(*size);
+		let b = random_big_uint(*size);
+
+		group.throughput(Throughput::Elements(*size as u64));
+		group.bench_with_input(BenchmarkId::new("mul", size), size, |bencher, _| {
+			bencher.iter(|| a.clone().mul(&b));
+		});
+		group.bench_with_input(BenchmarkId::new("mul_schoolbook", size), size, |bencher, _| {
+			bencher.iter(|| a.clone().mul_schoolbook(&b));
+		});
+	}
+}
+
 fn bench_division(c: &mut Criterion) {
 	let mut group = c.benchmark_group("division");
 
@@ -76,6 +93,6 @@ fn bench_division(c: &mut Criterion) {
 criterion_group! {
 	name = benches;
 	config = Criterion::default();
-	targets = bench_addition, bench_subtraction, bench_multiplication, bench_division
+	targets = bench_addition, bench_subtraction, bench_multiplication, bench_karatsuba, bench_division
 }
 criterion_main!(benches);
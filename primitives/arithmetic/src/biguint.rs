@@ -21,6 +21,9 @@ use codec::{Decode, Encode};
 use num_traits::{One, Zero};
 use sp_std::{cell::RefCell, cmp::Ordering, ops, prelude::*, vec};
 
+#[cfg(feature = "std")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
 // A sensible value for this would be half of the dword size of the host machine. Since the
 // runtime is compiled to 32bit webassembly, using 32 and 64 for single and double respectively
 // should yield the most performance.
@@ -34,9 +37,20 @@ const SHIFT: usize = 32;
 /// short form of _Base_. Analogous to the value 10 in base-10 decimal numbers.
 const B: Double = Single::max_value() as Double + 1;
 
+/// The largest power of ten that fits in a [`Single`], used for decimal conversions.
+const DECIMAL_CHUNK: Single = 1_000_000_000;
+/// Number of decimal digits held by each base-[`DECIMAL_CHUNK`] digit.
+const DECIMAL_CHUNK_DIGITS: usize = 9;
+/// Operands with at least this many limbs each are multiplied with Karatsuba's algorithm rather
+/// than the schoolbook one. Karatsuba needs fewer limb multiplications but has more overhead, and
+/// only pays off from about 40 limbs. See the `karatsuba` benchmarks for the crossover.
+pub const KARATSUBA_THRESHOLD: usize = 40;
+
 static_assertions::const_assert!(
 	sp_std::mem::size_of::<Double>() - sp_std::mem::size_of::<Single>() == SHIFT / 8
 );
+// Karatsuba splits the operands in halves, which must not be empty.
+static_assertions::const_assert!(KARATSUBA_THRESHOLD >= 2);
 
 /// Splits a [`Double`] limb number into a tuple of two [`Single`] limb numbers.
 pub fn split(a: Double) -> (Single, Single) {
@@ -78,6 +92,87 @@ fn div_single(a: Double, b: Single) -> (Double, Single) {
 	(q, r as Single)
 }
 
+/// Adds the product of `a` and `b` to `out`, with all limbs in little endian order.
+///
+/// With `a = a1 * B^h + a0` and `b = b1 * B^h + b0`, the product is `z2 * B^2h + z1 * B^h + z0`,
+/// where `z0 = a0 * b0`, `z2 = a1 * b1` and `z1 = (a0 + a1) * (b0 + b1) - z0 - z2`. That is three
+/// half-sized multiplications instead of four. Below [`KARATSUBA_THRESHOLD`] limbs, the
+/// schoolbook algorithm is used instead.
+///
+/// `out` must have `a.len() + b.len()` limbs, all zero.
+fn karatsuba(a: &[Single], b: &[Single], out: &mut [Single]) {
+	if a.len().min(b.len()) < KARATSUBA_THRESHOLD {
+		for (j, &x) in a.iter().enumerate() {
+			let mut k = 0;
+			for (i, &y) in b.iter().enumerate() {
+				// PROOF: (B−1) × (B−1) + (B−1) + (B−1) = B^2 −1 < B^2. addition is safe.
+				let t = mul_single(x, y) + Double::from(out[i + j]) + Double::from(k);
+				out[i + j] = (t % B) as Single;
+				k = (t / B) as Single;
+			}
+			out[j + b.len()] = k;
+		}
+		return
+	}
+
+	let h = a.len().min(b.len()) / 2;
+	let (a0, a1) = a.split_at(h);
+	let (b0, b1) = b.split_at(h);
+	// `z0` and `z2` do not overlap, so they are written to `out` directly.
+	let (z0, z2) = out.split_at_mut(2 * h);
+	karatsuba(a0, b0, z0);
+	karatsuba(a1, b1, z2);
+
+	let (sum_a, sum_b) = (add_limbs(a0, a1), add_limbs(b0, b1));
+	let mut z1 = vec![0; sum_a.len() + sum_b.len()];
+	karatsuba(&sum_a, &sum_b, &mut z1);
+	// PROOF: `(a0 + a1) * (b0 + b1) = z0 + z2 + a0 * b1 + a1 * b0`, the subtractions can't
+	// underflow, and `z1 * B^h` is part of the product so it fits in `out`.
+	sub_limbs_assign(&mut z1, &out[..2 * h]);
+	sub_limbs_assign(&mut z1, &out[2 * h..]);
+	while z1.last() == Some(&0) {
+		z1.pop();
+	}
+	add_limbs_assign(&mut out[h..], &z1);
+}
+
+/// Returns `a + b`, with all limbs in little endian order. The output has one more limb than the
+/// longest input.
+fn add_limbs(a: &[Single], b: &[Single]) -> Vec<Single> {
+	let mut out = a.to_vec();
+	out.resize(a.len().max(b.len()) + 1, 0);
+	add_limbs_assign(&mut out, b);
+	out
+}
+
+/// Adds `b` to `a` in place, with all limbs in little endian order. The sum must fit in `a`.
+fn add_limbs_assign(a: &mut [Single], b: &[Single]) {
+	let mut k = 0;
+	for i in 0..a.len() {
+		if i >= b.len() && k == 0 {
+			break
+		}
+		let s = Double::from(a[i]) + Double::from(b.get(i).copied().unwrap_or(0)) + k;
+		a[i] = (s % B) as Single;
+		k = s / B;
+	}
+}
+
+/// Subtracts `b` from `a` in place, with all limbs in little endian order. `b` must not be
+/// greater than `a`.
+fn sub_limbs_assign(a: &mut [Single], b: &[Single]) {
+	let mut borrow = false;
+	for i in 0..a.len() {
+		if i >= b.len() && !borrow {
+			break
+		}
+		let (d, b1) = a[i].overflowing_sub(b.get(i).copied().unwrap_or(0));
+		let (d, b2) = d.overflowing_sub(borrow as Single);
+		a[i] = d;
+		borrow = b1 || b2;
+	}
+}
+
 /// Simple wrapper around an infinitely large integer, represented as limbs of [`Single`].
 #[derive(Encode, Decode, Clone, Default)]
 pub struct BigUint {
@@ -181,6 +276,33 @@ impl BigUint {
 		self.digits = new_digits;
 	}
 
+	/// The number of significant bits of `self`, i.e. the position of the most significant set
+	/// bit plus one. Zero has no significant bits.
+	pub fn bits(&self) -> usize {
+		match self.digits.iter().position(|&d| d != 0) {
+			Some(i) => (self.len() - i) * SHIFT - self.digits[i].leading_zeros() as usize,
+			None => 0,
+		}
+	}
+
+	/// The number of leading zero bits of `self`, counted over all of its limbs, including any
+	/// leading zero limbs.
+	pub fn leading_zeros(&self) -> usize {
+		self.len() * SHIFT - self.bits()
+	}
+
+	/// Applies `f` to each pair of limbs of `self` and `other`, treating missing limbs as zero.
+	/// The output is stripped.
+	fn zip_limbs(self, other: &Self, f: impl Fn(Single, Single) -> Single) -> Self {
+		let n = self.len().max(other.len());
+		let mut out = Self::with_capacity(n);
+		for j in 0..n {
+			out.set(j, f(self.checked_get(j).unwrap_or(0), other.checked_get(j).unwrap_or(0)));
+		}
+		out.lstrip();
+		out
+	}
+
 	/// Adds `self` with `other`. self and other do not have to have any particular size. Given
 	/// that the `n = max{size(self), size(other)}`, it will produce a number with `n + 1`
 	/// limbs.
@@ -249,6 +371,36 @@ impl BigUint {
 		}
 	}
 
+	/// Subtracts `other` from `self`, returning `None` if `other` is bigger than `self`.
+	///
+	/// Like [`Self::sub`], this does not strip the output.
+	pub fn checked_sub(self, other: &Self) -> Option<Self> {
+		self.sub(other).ok()
+	}
+
+	/// Subtracts `other` from `self`, returning the result along with a boolean indicating
+	/// whether a borrow occurred.
+	///
+	/// If a borrow occurred, the result has wrapped around, i.e. it is `B^n - (other - self)`
+	/// where `n` is the number of limbs of the output, as with [`Self::sub`].
+	pub fn overflowing_sub(self, other: &Self) -> (Self, bool) {
+		match self.sub(other) {
+			Ok(w) => (w, false),
+			Err(w) => (w, true),
+		}
+	}
+
+	/// Returns the absolute difference between `self` and `other`.
+	///
+	/// Like [`Self::sub`], this does not strip the output.
+	pub fn abs_diff(self, other: &Self) -> Self {
+		if self >= *other {
+			self.sub(other).unwrap_or_else(|w| w)
+		} else {
+			other.clone().sub(&self).unwrap_or_else(|w| w)
+		}
+	}
+
 	/// Multiplies n-limb number `self` with m-limb number `other`.
 	///
 	/// The resulting number will always have `n + m` limbs.
@@ -256,8 +408,34 @@ impl BigUint {
 	/// This function does not strip the output and returns the original allocated `n + m`
 	/// limbs. The caller may strip the output if desired.
 	///
-	/// Taken from "The Art of Computer Programming" by D.E. Knuth, vol 2, chapter 4.
+	/// Operands of at least [`KARATSUBA_THRESHOLD`] limbs each are multiplied with Karatsuba's
+	/// algorithm, and smaller ones with [`Self::mul_schoolbook`].
 	pub fn mul(self, other: &Self) -> Self {
+		if self.len().min(other.len()) < KARATSUBA_THRESHOLD {
+			self.mul_schoolbook(other)
+		} else {
+			self.mul_karatsuba(other)
+		}
+	}
+
+	/// Karatsuba multiplication, see [`karatsuba`]. The output has `n + m` limbs, like
+	/// [`Self::mul`].
+	fn mul_karatsuba(self, other: &Self) -> Self {
+		// The limb helpers work on little endian limbs.
+		let a: Vec<Single> = self.digits.iter().rev().copied().collect();
+		let b: Vec<Single> = other.digits.iter().rev().copied().collect();
+		let mut digits = vec![0; a.len() + b.len()];
+		karatsuba(&a, &b, &mut digits);
+		digits.reverse();
+		Self { digits }
+	}
+
+	/// Multiplies n-limb number `self` with m-limb number `other`, always with the schoolbook
+	/// algorithm. The output is the same as [`Self::mul`], which should be preferred; this is
+	/// mostly useful to compare the two algorithms.
+	///
+	/// Taken from "The Art of Computer Programming" by D.E. Knuth, vol 2, chapter 4.
+	pub fn mul_schoolbook(self, other: &Self) -> Self {
 		let n = self.len();
 		let m = other.len();
 		let mut w = Self::with_capacity(m + n);
@@ -284,12 +462,42 @@ impl BigUint {
 		w
 	}
 
+	/// Returns the integer square root of `self`, i.e. `floor(sqrt(self))`, and whether it is
+	/// exact.
+	///
+	/// The root is found one bit at a time, from the most significant down, keeping each bit whose
+	/// inclusion does not push the square above `self`. The root is stripped.
+	pub fn sqrt(&self) -> (Self, bool) {
+		// The root of a number with `len` limbs has at most `ceil(len / 2)` limbs.
+		let mut root = Self::with_capacity((self.len() + 1) / 2);
+		let mut square = Self::zero();
+		for bit in (0..root.len() * SHIFT).rev() {
+			let (index, mask) = (bit / SHIFT, 1 << (bit % SHIFT));
+			let mut candidate = root.clone();
+			candidate.set(index, root.get(index) | mask);
+			let candidate_square = candidate.clone().mul(&candidate);
+			if candidate_square <= *self {
+				root = candidate;
+				square = candidate_square;
+			}
+		}
+		root.lstrip();
+		let exact = square == *self;
+		(root, exact)
+	}
+
 	/// Divides `self` by a single limb `other`. This can be used in cases where the original
 	/// division cannot work due to the divisor (`other`) being just one limb.
 	///
 	/// Invariant: `other` cannot be zero.
-	pub fn div_unit(self, mut other: Single) -> Self {
-		other = other.max(1);
+	pub fn div_unit(self, other: Single) -> Self {
+		self.div_rem_unit(other.max(1)).0
+	}
+
+	/// Divides `self` by a single limb `other`, returning the quotient and the remainder.
+	///
+	/// Invariant: `other` cannot be zero.
+	fn div_rem_unit(self, other: Single) -> (Self, Single) {
 		let n = self.len();
 		let mut out = Self::with_capacity(n);
 		let mut r: Single = 0;
@@ -300,6 +508,29 @@ impl BigUint {
 			out.set(d, q as Single);
 			r = rr;
 		}
+		(out, r)
+	}
+
+	/// The decimal representation of `self`.
+	#[cfg(feature = "std")]
+	pub fn to_decimal_string(&self) -> String {
+		// Split into base-`DECIMAL_CHUNK` digits, least significant first.
+		let mut chunks = Vec::new();
+		let mut rest = self.clone();
+		loop {
+			rest.lstrip();
+			let (quotient, chunk) = rest.div_rem_unit(DECIMAL_CHUNK);
+			chunks.push(chunk);
+			rest = quotient;
+			if rest.is_zero() {
+				break
+			}
+		}
+		let mut chunks = chunks.into_iter().rev();
+		let mut out = chunks.next().unwrap_or_default().to_string();
+		for chunk in chunks {
+			out.push_str(&format!("{:0width$}", chunk, width = DECIMAL_CHUNK_DIGITS));
+		}
 		out
 	}
 
@@ -455,6 +686,13 @@ impl sp_std::fmt::Debug for BigUint {
 	}
 }
 
+#[cfg(feature = "std")]
+impl sp_std::fmt::Display for BigUint {
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter<'_>) -> sp_std::fmt::Result {
+		f.pad_integral(true, "", &self.to_decimal_string())
+	}
+}
+
 impl PartialEq for BigUint {
 	fn eq(&self, other: &Self) -> bool {
 		self.cmp(other) == Ordering::Equal
@@ -493,6 +731,49 @@ impl PartialOrd for BigUint {
 	}
 }
 
+impl sp_std::str::FromStr for BigUint {
+	type Err = &'static str;
+
+	/// Parse a decimal string, with no sign and no separators.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+			return Err("invalid string input for BigUint")
+		}
+		let mut value = Self::zero();
+		for chunk in s.as_bytes().chunks(DECIMAL_CHUNK_DIGITS) {
+			let (scale, digits) = chunk.iter().fold((1 as Single, 0 as Single), |(s, d), b| {
+				(s * 10, d * 10 + Single::from(b - b'0'))
+			});
+			value = value.mul(&Self::from(scale)).add(&Self::from(digits));
+			value.lstrip();
+		}
+		Ok(value)
+	}
+}
+
+// Serialized as a decimal string, as JSON numbers cannot hold arbitrary precision integers.
+#[cfg(feature = "std")]
+impl Serialize for BigUint {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+		S: Serializer,
+	{
+		serializer.serialize_str(&self.to_decimal_string())
+	}
+}
+
+#[cfg(feature = "std")]
+impl<'de> Deserialize<'de> for BigUint {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: Deserializer<'de>,
+	{
+		use sp_std::str::FromStr;
+		let s = String::deserialize(deserializer)?;
+		BigUint::from_str(&s).map_err(de::Error::custom)
+	}
+}
+
 impl ops::Add for BigUint {
 	type Output = Self;
 	fn add(self, rhs: Self) -> Self::Output {
@@ -514,6 +795,58 @@ impl ops::Mul for BigUint {
 	}
 }
 
+impl ops::Shl<usize> for BigUint {
+	type Output = Self;
+	/// Shifts `self` left by `shift` bits. The output is stripped.
+	fn shl(self, shift: usize) -> Self::Output {
+		let (limbs, bits) = (shift / SHIFT, shift % SHIFT);
+		let n = self.len();
+		let mut out = Self::with_capacity(n + limbs + 1);
+		for i in 0..n {
+			// PROOF: a limb shifted by less than `SHIFT` bits fits in a double.
+			let (high, low) = split(Double::from(self.get(i)) << bits);
+			out.set(i + limbs, out.get(i + limbs) | low);
+			out.set(i + limbs + 1, high);
+		}
+		out.lstrip();
+		out
+	}
+}
+
+impl ops::Shr<usize> for BigUint {
+	type Output = Self;
+	/// Shifts `self` right by `shift` bits. The output is stripped.
+	fn shr(self, shift: usize) -> Self::Output {
+		let (limbs, bits) = (shift / SHIFT, shift % SHIFT);
+		let n = self.len();
+		if limbs >= n {
+			return Self::zero()
+		}
+		let mut out = Self::with_capacity(n - limbs);
+		for i in 0..n - limbs {
+			let low = Double::from(self.get(i + limbs));
+			let high = Double::from(self.checked_get(i + limbs + 1).unwrap_or(0));
+			out.set(i, (((high << SHIFT) | low) >> bits) as Single);
+		}
+		out.lstrip();
+		out
+	}
+}
+
+impl ops::BitAnd for BigUint {
+	type Output = Self;
+	fn bitand(self, rhs: Self) -> Self::Output {
+		self.zip_limbs(&rhs, |a, b| a & b)
+	}
+}
+
+impl ops::BitOr for BigUint {
+	type Output = Self;
+	fn bitor(self, rhs: Self) -> Self::Output {
+		self.zip_limbs(&rhs, |a, b| a | b)
+	}
+}
+
 impl Zero for BigUint {
 	fn zero() -> Self {
 		Self { digits: vec![Zero::zero()] }
@@ -580,6 +913,190 @@ impl From<u128> for BigUint {
 	}
 }
 
+/// A signed infinite precision integer, represented as a sign and a [`BigUint`] magnitude.
+///
+/// Zero is never negative, so every value has a single representation.
+#[derive(Clone, Default)]
+pub struct BigInt {
+	negative: bool,
+	magnitude: BigUint,
+}
+
+impl BigInt {
+	/// Create a new instance from a sign and a magnitude. The sign of zero is ignored.
+	pub fn from_parts(negative: bool, mut magnitude: BigUint) -> Self {
+		magnitude.lstrip();
+		let negative = negative && !magnitude.is_zero();
+		Self { negative, magnitude }
+	}
+
+	/// Whether `self` is below zero.
+	pub fn is_negative(&self) -> bool {
+		self.negative
+	}
+
+	/// The absolute value of `self`.
+	pub fn magnitude(&self) -> &BigUint {
+		&self.magnitude
+	}
+
+	/// Consume `self` and return its sign and magnitude.
+	pub fn into_parts(self) -> (bool, BigUint) {
+		(self.negative, self.magnitude)
+	}
+
+	/// Adds `other` to `self`.
+	pub fn add(self, other: &Self) -> Self {
+		self.add_with_sign(other.negative, &other.magnitude)
+	}
+
+	/// Subtracts `other` from `self`.
+	pub fn sub(self, other: &Self) -> Self {
+		self.add_with_sign(!other.negative, &other.magnitude)
+	}
+
+	/// Multiplies `self` with `other`.
+	pub fn mul(self, other: &Self) -> Self {
+		Self::from_parts(self.negative != other.negative, self.magnitude.mul(&other.magnitude))
+	}
+
+	/// Adds the value with the given sign and magnitude to `self`, using the unsigned limb
+	/// arithmetic of [`BigUint`] on the magnitudes.
+	fn add_with_sign(self, negative: bool, magnitude: &BigUint) -> Self {
+		if self.negative == negative {
+			return Self::from_parts(negative, self.magnitude.add(magnitude))
+		}
+		// The signs differ, so the result takes the sign of the larger magnitude.
+		match self.magnitude.clone().sub(magnitude) {
+			Ok(difference) => Self::from_parts(self.negative, difference),
+			Err(_) => Self::from_parts(
+				negative,
+				magnitude.clone().sub(&self.magnitude).unwrap_or_else(|e| e),
+			),
+		}
+	}
+}
+
+impl sp_std::fmt::Debug for BigInt {
+	fn fmt(&self, f: &mut sp_std::fmt::Formatter<'_>) -> sp_std::fmt::Result {
+		write!(f, "BigInt {{ negative: {:?}, magnitude: {:?} }}", self.negative, self.magnitude)
+	}
+}
+
+impl PartialEq for BigInt {
+	fn eq(&self, other: &Self) -> bool {
+		self.cmp(other) == Ordering::Equal
+	}
+}
+
+impl Eq for BigInt {}
+
+impl Ord for BigInt {
+	fn cmp(&self, other: &Self) -> Ordering {
+		match (self.negative, other.negative) {
+			(false, false) => self.magnitude.cmp(&other.magnitude),
+			(true, true) => other.magnitude.cmp(&self.magnitude),
+			(false, true) => Ordering::Greater,
+			(true, false) => Ordering::Less,
+		}
+	}
+}
+
+impl PartialOrd for BigInt {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl ops::Neg for BigInt {
+	type Output = Self;
+	fn neg(self) -> Self::Output {
+		Self::from_parts(!self.negative, self.magnitude)
+	}
+}
+
+impl ops::Add for BigInt {
+	type Output = Self;
+	fn add(self, rhs: Self) -> Self::Output {
+		self.add(&rhs)
+	}
+}
+
+impl ops::Sub for BigInt {
+	type Output = Self;
+	fn sub(self, rhs: Self) -> Self::Output {
+		self.sub(&rhs)
+	}
+}
+
+impl ops::Mul for BigInt {
+	type Output = Self;
+	fn mul(self, rhs: Self) -> Self::Output {
+		self.mul(&rhs)
+	}
+}
+
+impl Zero for BigInt {
+	fn zero() -> Self {
+		Self { negative: false, magnitude: Zero::zero() }
+	}
+
+	fn is_zero(&self) -> bool {
+		self.magnitude.is_zero()
+	}
+}
+
+impl One for BigInt {
+	fn one() -> Self {
+		Self { negative: false, magnitude: One::one() }
+	}
+}
+
+impl From<BigUint> for BigInt {
+	fn from(a: BigUint) -> Self {
+		Self::from_parts(false, a)
+	}
+}
+
+impl From<u128> for BigInt {
+	fn from(a: u128) -> Self {
+		Self::from_parts(false, a.into())
+	}
+}
+
+impl From<i128> for BigInt {
+	fn from(a: i128) -> Self {
+		Self::from_parts(a < 0, a.unsigned_abs().into())
+	}
+}
+
+impl TryFrom<BigInt> for u128 {
+	type Error = &'static str;
+	fn try_from(value: BigInt) -> Result<u128, Self::Error> {
+		if value.negative {
+			return Err("cannot fit a negative number into u128")
+		}
+		value.magnitude.try_into()
+	}
+}
+
+impl TryFrom<BigInt> for i128 {
+	type Error = &'static str;
+	fn try_from(value: BigInt) -> Result<i128, Self::Error> {
+		let error_message = "cannot fit a number into i128";
+		let magnitude = u128::try_from(value.magnitude).map_err(|_| error_message)?;
+		if value.negative {
+			// `i128::MIN` has no positive counterpart, so negate in unsigned arithmetic.
+			match magnitude.cmp(&i128::MIN.unsigned_abs()) {
+				Ordering::Greater => Err(error_message),
+				_ => Ok(magnitude.wrapping_neg() as i128),
+			}
+		} else {
+			i128::try_from(magnitude).map_err(|_| error_message)
+		}
+	}
+}
+
 #[cfg(test)]
 pub mod tests {
 	use super::*;
@@ -750,4 +1267,235 @@ pub mod tests {
 		assert_eq!(c.clone().div_unit(2), BigUint { digits: vec![7, 14, 50] });
 		assert_eq!(c.clone().div_unit(7), BigUint { digits: vec![2, 4, 14] });
 	}
+
+	#[test]
+	fn big_int_arithmetic_works() {
+		let big = |x: i128| BigInt::from(x);
+		let cases = [0, 1, -1, 7, -7, 1 << 40, -(1 << 40), i64::MAX as i128, i64::MIN as i128];
+		for a in cases {
+			for b in cases {
+				assert_eq!(big(a).add(&big(b)), big(a + b), "{} + {}", a, b);
+				assert_eq!(big(a).sub(&big(b)), big(a - b), "{} - {}", a, b);
+				assert_eq!(big(a).mul(&big(b)), big(a * b), "{} * {}", a, b);
+				assert_eq!(big(a).cmp(&big(b)), a.cmp(&b), "{} cmp {}", a, b);
+			}
+		}
+		assert_eq!(-big(5), big(-5));
+		assert_eq!(-BigInt::zero(), BigInt::zero());
+		assert!(!(-BigInt::zero()).is_negative());
+		assert!(!big(5).sub(&big(5)).is_negative());
+	}
+
+	#[test]
+	fn big_int_conversions_work() {
+		for x in [0, 1, -1, i128::MAX, i128::MIN, i128::MIN + 1] {
+			assert_eq!(i128::try_from(BigInt::from(x)), Ok(x));
+		}
+		assert_eq!(u128::try_from(BigInt::from(u128::MAX)), Ok(u128::MAX));
+		assert_eq!(i128::try_from(BigInt::from(u128::MAX)), Err("cannot fit a number into i128"));
+		assert_eq!(
+			i128::try_from(BigInt::from(i128::MIN).sub(&BigInt::one())),
+			Err("cannot fit a number into i128")
+		);
+		assert_eq!(
+			u128::try_from(BigInt::from(-1i128)),
+			Err("cannot fit a negative number into u128")
+		);
+
+		// A result that only fits once the negative term is subtracted.
+		let max = BigInt::from(u128::MAX);
+		let sum = max.clone().add(&max).sub(&max);
+		assert_eq!(u128::try_from(sum), Ok(u128::MAX));
+		assert_eq!(BigInt::from(BigUint::from(5u32)), BigInt::from(5u128));
+		assert_eq!(BigInt::from_parts(true, BigUint::zero()), BigInt::zero());
+	}
+
+	#[test]
+	fn checked_and_overflowing_sub_work() {
+		let a = BigUint::from(10 as Single);
+		let b = BigUint::from(13 as Single);
+
+		assert_eq!(a.clone().checked_sub(&a), Some(BigUint::zero()));
+		assert_eq!(b.clone().checked_sub(&a), Some(BigUint::from(3 as Single)));
+		assert_eq!(a.clone().checked_sub(&b), None);
+
+		assert_eq!(b.clone().overflowing_sub(&a), (BigUint::from(3 as Single), false));
+		assert_eq!(a.clone().overflowing_sub(&b), (BigUint::from((B - 3) as Single), true));
+
+		// Operands of different sizes.
+		let c = BigUint::from(u64::MAX);
+		assert_eq!(c.clone().checked_sub(&a), Some(BigUint::from(u64::MAX - 10)));
+		assert_eq!(a.clone().checked_sub(&c), None);
+		assert_eq!(a.clone().overflowing_sub(&c), (BigUint::from(11u64), true));
+	}
+
+	#[test]
+	fn abs_diff_works() {
+		let a = BigUint::from(10 as Single);
+		let b = BigUint::from(13 as Single);
+		assert_eq!(a.clone().abs_diff(&b), BigUint::from(3 as Single));
+		assert_eq!(b.clone().abs_diff(&a), BigUint::from(3 as Single));
+		assert_eq!(a.clone().abs_diff(&a), BigUint::zero());
+
+		let c = BigUint::from(u128::MAX);
+		assert_eq!(a.clone().abs_diff(&c), BigUint::from(u128::MAX - 10));
+		assert_eq!(c.clone().abs_diff(&a), BigUint::from(u128::MAX - 10));
+	}
+
+	#[test]
+	fn sqrt_works() {
+		let sqrt = |n: u128| {
+			let (root, exact) = BigUint::from(n).sqrt();
+			(u128::try_from(root).unwrap(), exact)
+		};
+		assert_eq!(sqrt(0), (0, true));
+		assert_eq!(sqrt(1), (1, true));
+		assert_eq!(sqrt(2), (1, false));
+		assert_eq!(sqrt(15), (3, false));
+		assert_eq!(sqrt(16), (4, true));
+		assert_eq!(sqrt(u64::MAX as u128 * u64::MAX as u128), (u64::MAX as u128, true));
+		assert_eq!(sqrt(u128::MAX), (u64::MAX as u128, false));
+
+		// Roots wider than a `u128`.
+		let root = BigUint::from(u128::MAX).mul(&BigUint::from(3u64));
+		let mut square = root.clone().mul(&root);
+		square.lstrip();
+		assert_eq!(square.sqrt(), (root.clone(), true));
+		let below = square.clone().sub(&BigUint::one()).unwrap();
+		assert_eq!(below.sqrt(), (root.clone().sub(&BigUint::one()).unwrap(), false));
+		let above = square.add(&BigUint::one());
+		assert_eq!(above.sqrt(), (root, false));
+
+		// Leading zero limbs do not matter.
+		assert_eq!(BigUint::from_limbs(&[0, 0, 0, 49]).sqrt(), (BigUint::from(7u64), true));
+	}
+
+	#[test]
+	fn karatsuba_matches_schoolbook() {
+		let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+		let mut limbs = |n: usize| -> BigUint {
+			let digits: Vec<Single> = (0..n)
+				.map(|_| {
+					seed ^= seed << 13;
+					seed ^= seed >> 7;
+					seed ^= seed << 17;
+					seed as Single
+				})
+				.collect();
+			BigUint::from_limbs(&digits)
+		};
+		let t = KARATSUBA_THRESHOLD;
+		for (n, m) in
+			[(t, t), (t + 1, t), (2 * t + 3, 2 * t), (5 * t, t), (t, 3 * t + 1), (4 * t, 4 * t)]
+		{
+			let (a, b) = (limbs(n), limbs(m));
+			let product = a.clone().mul(&b);
+			assert_eq!(product.len(), n + m);
+			assert_eq!(product.digits, a.mul_schoolbook(&b).digits, "{} x {}", n, m);
+		}
+
+		let max = BigUint::from_limbs(&vec![Single::MAX; 3 * t]);
+		assert_eq!(max.clone().mul(&max).digits, max.clone().mul_schoolbook(&max).digits);
+		let mut zeros = BigUint::from_limbs(&vec![0; 2 * t]);
+		zeros.set(t, 1);
+		assert_eq!(zeros.clone().mul(&max).digits, zeros.mul_schoolbook(&max).digits);
+		let zero = BigUint::from_limbs(&vec![0; t]);
+		assert_eq!(zero.clone().mul(&max).digits, vec![0; 4 * t]);
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn decimal_string_round_trip_works() {
+		use sp_std::str::FromStr;
+		let cases = [
+			"0",
+			"7",
+			"1000000000",
+			"999999999",
+			"4294967296",
+			"340282366920938463463374607431768211455",
+			"115792089237316195423570985008687907852589419931798687112530834793049593217025",
+		];
+		for s in cases {
+			let value = BigUint::from_str(s).unwrap();
+			assert_eq!(value.to_decimal_string(), s);
+			assert_eq!(value.to_string(), s);
+		}
+		assert_eq!(BigUint::from_str("4294967296").unwrap(), BigUint::from(1u64 << 32));
+		assert_eq!(BigUint::from(u128::MAX).to_decimal_string(), u128::MAX.to_string());
+		assert_eq!(BigUint::from_limbs(&[0, 0, 5]).to_decimal_string(), "5");
+		assert_eq!(BigUint::from_str("007").unwrap(), BigUint::from(7 as Single));
+
+		for s in ["", "-1", "+1", "1_000", "1.5", " 1", "0x10"] {
+			assert_eq!(BigUint::from_str(s), Err("invalid string input for BigUint"));
+		}
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn serde_round_trip_works() {
+		let s = "115792089237316195423570985008687907852589419931798687112530834793049593217025";
+		let big: BigUint = s.parse().unwrap();
+		let json = serde_json::to_string(&big).unwrap();
+		assert_eq!(json, format!("\"{}\"", s));
+		assert_eq!(serde_json::from_str::<BigUint>(&json).unwrap(), big);
+		assert_eq!(serde_json::to_string(&BigUint::zero()).unwrap(), "\"0\"");
+
+		// invalid strings and bare JSON numbers are rejected.
+		let error = serde_json::from_str::<BigUint>("\"12a\"").unwrap_err();
+		assert!(error.to_string().contains("invalid string input for BigUint"));
+		assert!(serde_json::from_str::<BigUint>("\"-1\"").is_err());
+		assert!(serde_json::from_str::<BigUint>("12").is_err());
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn display_works() {
+		let big: BigUint = "1000000000000000000000000000000".parse().unwrap();
+		assert_eq!(big, BigUint::from(10u128.pow(15)).mul(&BigUint::from(10u128.pow(15))));
+		assert_eq!(format!("{}", big), "1000000000000000000000000000000");
+		assert_eq!(format!("{:>8}", BigUint::from(42u64)), "      42");
+		assert_eq!(format!("{:08}", BigUint::from(42u64)), "00000042");
+		assert_eq!(format!("{:+}", BigUint::zero()), "+0");
+	}
+
+	#[test]
+	fn bits_and_leading_zeros_work() {
+		assert_eq!(BigUint::zero().bits(), 0);
+		assert_eq!(BigUint::zero().leading_zeros(), SHIFT);
+		assert_eq!(BigUint::one().bits(), 1);
+		assert_eq!(BigUint::from(u64::MAX).bits(), 64);
+		assert_eq!(BigUint::from(u64::MAX).leading_zeros(), 0);
+		assert_eq!(BigUint::from(1u128 << 100).bits(), 101);
+		assert_eq!(BigUint::from_limbs(&[0, 0, 1, 0]).bits(), SHIFT + 1);
+		assert_eq!(BigUint::from_limbs(&[0, 0, 1, 0]).leading_zeros(), 3 * SHIFT - 1);
+	}
+
+	#[test]
+	fn shifts_work() {
+		let x = 0x1234_5678_9abc_def0_1122_3344_5566_7788_u128;
+		for shift in [0, 1, 4, 31, 32, 33, 64, 95, 127] {
+			let shifted = BigUint::from(x >> shift);
+			assert_eq!(BigUint::from(x) >> shift, shifted, "{}", shift);
+			assert_eq!(shifted.clone() << shift, BigUint::from((x >> shift) << shift), "{}", shift);
+		}
+		assert_eq!(BigUint::from(x) >> 128, BigUint::zero());
+		assert_eq!(BigUint::from(x) >> 1000, BigUint::zero());
+
+		// Shifting left does not truncate.
+		let shifted = BigUint::from(u128::MAX) << 100;
+		assert_eq!(shifted.bits(), 228);
+		assert_eq!(shifted >> 100, BigUint::from(u128::MAX));
+		assert_eq!((BigUint::one() << 64).digits, vec![1, 0, 0]);
+	}
+
+	#[test]
+	fn bitwise_ops_work() {
+		let (a, b) = ((0xff00_ff00_u128 << 64) | 0xf0f0, 0x0ff0_u128);
+		assert_eq!(BigUint::from(a) & BigUint::from(b), BigUint::from(a & b));
+		assert_eq!(BigUint::from(a) | BigUint::from(b), BigUint::from(a | b));
+		assert_eq!((BigUint::from(a) & BigUint::from(b)).len(), 1);
+		assert_eq!(BigUint::from(a) & BigUint::zero(), BigUint::zero());
+		assert_eq!(BigUint::from(a) | BigUint::zero(), BigUint::from(a));
+	}
 }
@@ -18,12 +18,15 @@
 //! Decimal Fixed Point implementations for Substrate runtime.
 
 use crate::{
-	helpers_128bit::multiply_by_rational,
+	helpers_128bit::{
+		multiply_by_rational, multiply_by_rational_with_remainder,
+		multiply_by_rational_with_rounding, sqrt_of_product,
+	},
 	traits::{
 		Bounded, CheckedAdd, CheckedDiv, CheckedMul, CheckedNeg, CheckedSub, One,
-		SaturatedConversion, Saturating, UniqueSaturatedInto, Zero,
+		SaturatedConversion, Saturating, SaturatingFrom, UniqueSaturatedInto, Zero,
 	},
-	PerThing,
+	PerThing, Rounding,
 };
 use codec::{CompactAs, Decode, Encode};
 use sp_std::{
@@ -213,6 +216,35 @@ pub trait FixedPointNumber:
 		}
 	}
 
+	/// Checked fused multiply-add. Equal to `self * a + b`.
+	///
+	/// The result is computed exactly and rounded towards zero only once, so it can differ from
+	/// `self * a + b` by the rounding of the product. Returns `None` if the result does not fit.
+	fn checked_mul_add(self, a: Self, b: Self) -> Option<Self> {
+		let result = mul_add_i129(self.into_inner(), a.into_inner(), b.into_inner(), Self::DIV);
+		result
+			.value
+			.and_then(|value| from_i129(I129 { value, negative: result.negative }))
+			.map(Self::from_inner)
+	}
+
+	/// Saturating fused multiply-add. Equal to `self * a + b`.
+	///
+	/// Like [`Self::checked_mul_add`], this rounds only once, and it only saturates if the final
+	/// result does not fit: an overflowing `self * a` can still be brought back in range by `b`.
+	/// Returns `Self::min` or `Self::max` if the result does not fit.
+	fn mul_add(self, a: Self, b: Self) -> Self {
+		let result = mul_add_i129(self.into_inner(), a.into_inner(), b.into_inner(), Self::DIV);
+		match result
+			.value
+			.and_then(|value| from_i129(I129 { value, negative: result.negative }))
+		{
+			Some(inner) => Self::from_inner(inner),
+			None if result.negative => Self::min_value(),
+			None => Self::max_value(),
+		}
+	}
+
 	/// Saturating absolute value.
 	///
 	/// Returns `Self::max` if `self == Self::min`.
@@ -351,6 +383,47 @@ fn to_bound<N: FixedPointOperand, D: FixedPointOperand, R: Bounded>(n: N, m: D)
 	}
 }
 
+/// Result of [`mul_add_i129`]: the sign of the result and its magnitude, if it fits in a `u128`.
+struct MulAddResult {
+	value: Option<u128>,
+	negative: bool,
+}
+
+/// Returns `x * y / div + z`, rounded towards zero.
+fn mul_add_i129<N: FixedPointOperand>(x: N, y: N, z: N, div: N) -> MulAddResult {
+	let x: I129 = x.into();
+	let y: I129 = y.into();
+	let z: I129 = z.into();
+	let product_negative = x.negative != y.negative;
+
+	// The product has the sign of `x * y`, and is `quotient + remainder / div` in magnitude. If
+	// `quotient` does not fit in a `u128`, neither does the result: `z` could only bring it back in
+	// range if it had the opposite sign, in which case `|z| <= 2^127`.
+	let div: u128 = div.unique_saturated_into();
+	let (quotient, remainder) = match multiply_by_rational_with_remainder(x.value, y.value, div) {
+		Some(qr) => qr,
+		None => return MulAddResult { value: None, negative: product_negative },
+	};
+
+	// Add the integer part of the product to `z`.
+	let (value, negative) = if product_negative == z.negative {
+		(quotient.checked_add(z.value), z.negative)
+	} else if quotient >= z.value {
+		(Some(quotient - z.value), product_negative)
+	} else {
+		(Some(z.value - quotient), z.negative)
+	};
+
+	// The fraction of the product moves the sum towards zero if their signs differ, in which
+	// case rounding towards zero takes one unit off the magnitude. It cannot cross zero since it
+	// is less than one unit.
+	let value = match value {
+		Some(v) if remainder > 0 && v > 0 && negative != product_negative => Some(v - 1),
+		value => value,
+	};
+	MulAddResult { value, negative: negative && value != Some(0) }
+}
+
 macro_rules! implement_fixed {
 	(
 		$name:ident,
@@ -411,6 +484,11 @@ macro_rules! implement_fixed {
 				Self(inner)
 			}
 
+			/// const version of `FixedPointNumber::into_inner`.
+			pub const fn into_inner(self) -> $inner_type {
+				self.0
+			}
+
 			#[cfg(any(feature = "std", test))]
 			pub fn from_float(x: f64) -> Self {
 				Self((x * (<Self as FixedPointNumber>::DIV as f64)) as $inner_type)
@@ -420,6 +498,93 @@ macro_rules! implement_fixed {
 			pub fn to_float(self) -> f64 {
 				self.0 as f64 / <Self as FixedPointNumber>::DIV as f64
 			}
+
+			/// Parse a decimal string such as `"1.337"`, which is the format of
+			/// [`Self::to_decimal_string`].
+			///
+			/// At most as many fractional digits as the type has decimals are accepted, so that
+			/// parsing is exact. Exponents, a leading `+` and a missing integral or fractional part
+			/// around the `.` are rejected.
+			pub fn try_from_str(s: &str) -> Result<Self, &'static str> {
+				const INVALID: &str = "invalid string input for fixed point number";
+				const OUT_OF_RANGE: &str = "fixed point number out of range";
+
+				let (negative, s) = match s.strip_prefix('-') {
+					Some(_) if !$signed => return Err(OUT_OF_RANGE),
+					Some(rest) => (true, rest),
+					None => (false, s),
+				};
+				let (integral, fractional) = match s.split_once('.') {
+					Some((_, "")) => return Err(INVALID),
+					Some(parts) => parts,
+					None => (s, ""),
+				};
+				if integral.is_empty() {
+					return Err(INVALID)
+				}
+
+				let digit = |c: u8| {
+					if c.is_ascii_digit() {
+						Ok((c - b'0') as $inner_type)
+					} else {
+						Err(INVALID)
+					}
+				};
+				// Negative values are accumulated downwards, so that the minimum value parses.
+				let add = |acc: $inner_type, d: $inner_type| {
+					if negative {
+						acc.checked_sub(d)
+					} else {
+						acc.checked_add(d)
+					}
+				};
+
+				let mut inner: $inner_type = 0;
+				for c in integral.bytes() {
+					let d = digit(c)?;
+					inner = inner.checked_mul(10).and_then(|i| add(i, d)).ok_or(OUT_OF_RANGE)?;
+				}
+				inner = inner.checked_mul(<Self as FixedPointNumber>::DIV).ok_or(OUT_OF_RANGE)?;
+
+				let mut scale = <Self as FixedPointNumber>::DIV;
+				for c in fractional.bytes() {
+					scale /= 10;
+					if scale == 0 {
+						return Err("too many decimals for fixed point number")
+					}
+					inner = add(inner, digit(c)? * scale).ok_or(OUT_OF_RANGE)?;
+				}
+
+				Ok(Self(inner))
+			}
+
+			/// The shortest decimal representation, e.g. `"1.337"` or `"2"`, which
+			/// [`Self::try_from_str`] parses back to the same value.
+			///
+			/// Unlike `Display`, which prints the inner value.
+			#[cfg(feature = "std")]
+			pub fn to_decimal_string(&self) -> String {
+				let div = <Self as FixedPointNumber>::DIV;
+				let integral = self.0 / div;
+				// The sign is lost in the integral part between minus one and zero.
+				let sign = if integral == 0 && self.is_negative() { "-" } else { "" };
+				let mut fractional = ((self.0 % div) as i128).abs();
+				if fractional == 0 {
+					return format!("{}{}", sign, integral)
+				}
+
+				let mut width = 0;
+				let mut d = div;
+				while d > 1 {
+					d /= 10;
+					width += 1;
+				}
+				while fractional % 10 == 0 {
+					fractional /= 10;
+					width -= 1;
+				}
+				format!("{}{}.{:0>width$}", sign, integral, fractional, width = width)
+			}
 		}
 
 		impl Saturating for $name {
@@ -602,6 +767,23 @@ macro_rules! implement_fixed {
 			}
 		}
 
+		/// Converts between fixed point types, rounding to the nearest value when `F` has more
+		/// precision, and saturating when the value is out of range.
+		impl<F: FixedPointNumber> SaturatingFrom<F> for $name {
+			fn saturating_from(x: F) -> Self {
+				let n: I129 = x.into_inner().into();
+				multiply_by_rational_with_rounding(
+					n.value,
+					Self::DIV.unique_saturated_into(),
+					F::DIV.unique_saturated_into(),
+					Rounding::Nearest,
+				)
+				.and_then(|value| from_i129(I129 { value, negative: n.negative }))
+				.map(Self::from_inner)
+				.unwrap_or_else(|| to_bound(x.into_inner(), 1))
+			}
+		}
+
 		#[cfg(feature = "std")]
 		impl sp_std::fmt::Display for $name {
 			fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
@@ -609,6 +791,8 @@ macro_rules! implement_fixed {
 			}
 		}
 
+		/// Parses the inner value, as printed by `Display` and used in the serde encoding. Use
+		/// [`Self::try_from_str`] to parse the decimal representation.
 		#[cfg(feature = "std")]
 		impl sp_std::str::FromStr for $name {
 			type Err = &'static str;
@@ -628,7 +812,7 @@ macro_rules! implement_fixed {
 			where
 				S: Serializer,
 			{
-				serializer.serialize_str(&self.to_string())
+				serializer.serialize_str(&self.0.to_string())
 			}
 		}
 
@@ -640,9 +824,11 @@ macro_rules! implement_fixed {
 			where
 				D: Deserializer<'de>,
 			{
-				use sp_std::str::FromStr;
 				let s = String::deserialize(deserializer)?;
-				$name::from_str(&s).map_err(de::Error::custom)
+				let inner: $inner_type = s.parse().map_err(|_| {
+					de::Error::custom("invalid string input for fixed point number")
+				})?;
+				Ok(Self::from_inner(inner))
 			}
 		}
 
@@ -1397,6 +1583,72 @@ macro_rules! implement_fixed {
 				}
 			}
 
+			#[test]
+			fn mul_add_works() {
+				let inner_max = <$name as FixedPointNumber>::Inner::max_value();
+				let inner_min = <$name as FixedPointNumber>::Inner::min_value();
+				let half = $name::saturating_from_rational(1, 2);
+				let two = $name::saturating_from_integer(2);
+
+				let a = $name::saturating_from_integer(3);
+				let b = $name::saturating_from_integer(4);
+				assert_eq!(two.mul_add(a, b), $name::saturating_from_integer(10));
+				assert_eq!(two.checked_mul_add(a, b), Some($name::saturating_from_integer(10)));
+				assert_eq!($name::zero().mul_add(a, b), b);
+
+				// The product is rounded with the addition, towards zero.
+				assert_eq!($name::from_inner(3).mul_add(half, $name::zero()), $name::from_inner(1));
+				assert_eq!(
+					$name::from_inner(3).mul_add(half, $name::from_inner(1)),
+					$name::from_inner(2)
+				);
+
+				// Overflow.
+				let max = $name::max_value();
+				assert_eq!(max.mul_add($name::one(), $name::zero()), max);
+				assert_eq!(max.checked_mul_add(two, $name::zero()), None);
+				assert_eq!(max.mul_add(two, $name::zero()), max);
+				assert_eq!(max.checked_mul_add($name::one(), $name::from_inner(1)), None);
+				assert_eq!(max.mul_add($name::one(), $name::from_inner(1)), max);
+
+				if $name::SIGNED {
+					// A single rounding: `-0.5 + 1` is rounded to zero, rather than `0 + 1`.
+					let minus_half = $name::saturating_from_rational(-1, 2);
+					let one = $name::from_inner(1);
+					assert_eq!(one.mul_add(minus_half, one), $name::zero());
+					assert_eq!(one.saturating_mul(minus_half).saturating_add(one), one);
+					assert_eq!(
+						$name::from_inner(3).mul_add(minus_half, $name::from_inner(2)),
+						$name::zero()
+					);
+					let minus_one = $name::zero().saturating_sub(one);
+					assert_eq!($name::from_inner(3).mul_add(minus_half, $name::zero()), minus_one);
+					assert_eq!(
+						$name::from_inner(3).mul_add(minus_half, minus_one),
+						minus_one.saturating_mul(two)
+					);
+
+					// The product may overflow as long as the result does not.
+					let min = $name::min_value();
+					assert_eq!(max.mul_add(two, min), $name::from_inner(inner_max - 1));
+					assert_eq!(
+						max.checked_mul_add(two, min),
+						Some($name::from_inner(inner_max - 1))
+					);
+					assert_eq!(
+						$name::from_inner(inner_min + 2).mul_add(two, max),
+						$name::from_inner(inner_min + 3)
+					);
+					assert_eq!(min.mul_add(minus_half, min), min / two);
+
+					assert_eq!(min.checked_mul_add(two, $name::zero()), None);
+					assert_eq!(min.mul_add(two, $name::zero()), min);
+					assert_eq!(max.mul_add($name::zero().saturating_sub(two), $name::zero()), min);
+				} else {
+					assert_eq!(max.mul_add(two, max), max);
+				}
+			}
+
 			#[test]
 			fn saturating_pow_should_work() {
 				assert_eq!(
@@ -1729,6 +1981,95 @@ macro_rules! implement_fixed {
 					);
 				}
 			}
+
+			#[test]
+			fn try_from_str_works() {
+				assert_eq!($name::try_from_str("0"), Ok($name::zero()));
+				assert_eq!($name::try_from_str("1"), Ok($name::one()));
+				assert_eq!(
+					$name::try_from_str("001.50"),
+					Ok($name::saturating_from_rational(3, 2))
+				);
+				assert_eq!(
+					$name::try_from_str("1.337"),
+					Ok($name::saturating_from_rational(1337, 1000))
+				);
+
+				// The smallest step parses, anything finer does not.
+				let smallest = format!("0.{}1", "0".repeat(precision() - 1));
+				assert_eq!($name::try_from_str(&smallest), Ok($name::from_inner(1)));
+				let too_precise = format!("0.{}1", "0".repeat(precision()));
+				assert!($name::try_from_str(&too_precise).is_err());
+
+				for invalid in ["", ".", "1.", ".5", "+1", "1.2.3", "a", "1e3", " 1", "--1"] {
+					assert!($name::try_from_str(invalid).is_err(), "{:?} parsed", invalid);
+				}
+				assert!($name::try_from_str("100000000000000000000000000000000000000000").is_err());
+
+				if $name::SIGNED {
+					assert_eq!($name::try_from_str("-0"), Ok($name::zero()));
+					assert_eq!(
+						$name::try_from_str("-3.14"),
+						Ok($name::saturating_from_rational(-314, 100))
+					);
+				} else {
+					assert!($name::try_from_str("-1").is_err());
+				}
+			}
+
+			#[test]
+			#[cfg(feature = "std")]
+			fn decimal_string_round_trips() {
+				assert_eq!($name::zero().to_decimal_string(), "0");
+				assert_eq!($name::saturating_from_integer(2).to_decimal_string(), "2");
+				assert_eq!(
+					$name::saturating_from_rational(1337, 1000).to_decimal_string(),
+					"1.337"
+				);
+				assert_eq!(
+					$name::from_inner(1).to_decimal_string(),
+					format!("0.{}1", "0".repeat(precision() - 1))
+				);
+				if $name::SIGNED {
+					assert_eq!($name::saturating_from_rational(-1, 2).to_decimal_string(), "-0.5");
+					assert_eq!(
+						$name::saturating_from_rational(-314, 100).to_decimal_string(),
+						"-3.14"
+					);
+				}
+
+				for x in [
+					min(),
+					max(),
+					$name::from_inner(1),
+					$name::saturating_from_rational(1, 3),
+					$name::saturating_from_rational(-22, 7),
+				] {
+					assert_eq!($name::try_from_str(&x.to_decimal_string()), Ok(x));
+				}
+			}
+
+			#[test]
+			#[cfg(feature = "std")]
+			fn from_str_parses_the_inner_value() {
+				let x = $name::saturating_from_rational(1337, 1000);
+				assert_eq!(x.to_string(), x.into_inner().to_string());
+				assert_eq!(x.into_inner().to_string().parse::<$name>(), Ok(x));
+
+				// `Display` and `FromStr` round trip.
+				for x in [min(), max(), $name::zero(), $name::saturating_from_integer(2), x] {
+					assert_eq!(x.to_string().parse::<$name>(), Ok(x));
+				}
+
+				// the same format as serde.
+				let json = serde_json::to_string(&x).unwrap();
+				assert_eq!(json, format!("\"{}\"", x.into_inner()));
+				assert_eq!(serde_json::from_str::<$name>(&json).unwrap(), x);
+
+				assert_eq!("1".parse::<$name>(), Ok($name::from_inner(1)));
+				assert!("1.337".parse::<$name>().is_err());
+				assert!("".parse::<$name>().is_err());
+			}
 		}
 	};
 }
@@ -1761,3 +2102,204 @@ implement_fixed!(
 	"_Fixed Point 128 bits unsigned, range = \
 		[0.000000000000000000, 340282366920938463463.374607431768211455]_",
 );
+
+impl FixedU128 {
+	/// Returns the square root, rounded to the nearest representable value.
+	///
+	/// The root is taken of the real value rather than the inner one, i.e. this is
+	/// `sqrt(inner * DIV)`, so no fractional precision is lost.
+	pub fn sqrt(self) -> Self {
+		Self(sqrt_of_product(self.0, <Self as FixedPointNumber>::DIV, Rounding::Nearest))
+	}
+}
+
+/// An exponential moving average of fixed point samples.
+///
+/// Each update moves the average towards the new sample by a fraction `alpha` of the distance
+/// between them, i.e. to `average + alpha * (sample - average)`, so a larger `alpha` forgets older
+/// samples faster. The average always stays between its previous value and the sample, so it can
+/// never overflow.
+#[derive(
+	Encode,
+	Decode,
+	Default,
+	Copy,
+	Clone,
+	codec::MaxEncodedLen,
+	PartialEq,
+	Eq,
+	Debug,
+	scale_info::TypeInfo,
+)]
+pub struct Ema<F> {
+	average: F,
+}
+
+impl<F: FixedPointNumber> Ema<F> {
+	/// Start averaging from `initial`.
+	pub fn new(initial: F) -> Self {
+		Self { average: initial }
+	}
+
+	/// The current average.
+	pub fn average(&self) -> F {
+		self.average
+	}
+
+	/// Move the average towards `sample` by `alpha` of the distance, and return the new average.
+	///
+	/// The step is computed with a single widened multiplication and rounded to the nearest, so
+	/// steps of less than half the smallest representable value are lost.
+	pub fn update<P: PerThing>(&mut self, sample: F, alpha: P) -> F {
+		let current: I129 = self.average.into_inner().into();
+		let target: I129 = sample.into_inner().into();
+		let towards_negative = sample < self.average;
+		// The distance between any two values of the same type fits in a `u128`.
+		let distance = if current.negative == target.negative {
+			current.value.max(target.value) - current.value.min(target.value)
+		} else {
+			current.value + target.value
+		};
+		let step = multiply_by_rational_with_rounding(
+			distance,
+			alpha.deconstruct().into(),
+			P::ACCURACY.into(),
+			Rounding::Nearest,
+		)
+		.unwrap_or(distance);
+
+		let next = if current.negative == towards_negative {
+			I129 { value: current.value + step, negative: current.negative }
+		} else if current.value >= step {
+			I129 { value: current.value - step, negative: current.negative }
+		} else {
+			I129 { value: step - current.value, negative: towards_negative }
+		};
+		// `step` is at most `distance`, so `next` is between the average and `sample`.
+		self.average = from_i129(next).map(F::from_inner).unwrap_or(sample);
+		self.average
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Perbill, Percent, Permill};
+
+	#[test]
+	fn ema_works() {
+		let fixed = |x: u128| FixedU128::saturating_from_integer(x);
+		let mut ema = Ema::new(fixed(10));
+		assert_eq!(ema.update(fixed(20), Percent::from_percent(10)), fixed(11));
+		assert_eq!(ema.update(fixed(1), Percent::from_percent(50)), fixed(6));
+		assert_eq!(ema.update(fixed(100), Percent::zero()), fixed(6));
+		assert_eq!(ema.update(fixed(100), Percent::one()), fixed(100));
+		assert_eq!(ema.average(), fixed(100));
+
+		// converges towards a constant sample without overshooting, until the steps round to zero.
+		let mut ema = Ema::new(FixedU128::zero());
+		for _ in 0..1000 {
+			let average = ema.update(fixed(7), Permill::from_percent(5));
+			assert!(average <= fixed(7));
+		}
+		assert_eq!(ema.average(), fixed(7) - FixedU128::from_inner(10));
+
+		// the step is rounded to the nearest.
+		let mut ema = Ema::new(FixedU128::from_inner(0));
+		assert_eq!(
+			ema.update(FixedU128::from_inner(3), Percent::from_percent(50)),
+			FixedU128::from_inner(1)
+		);
+		assert_eq!(
+			ema.update(FixedU128::from_inner(0), Percent::from_percent(10)),
+			FixedU128::from_inner(1)
+		);
+
+		// crossing zero and the full range of a signed type.
+		let mut ema = Ema::new(FixedI128::saturating_from_integer(-5));
+		assert_eq!(
+			ema.update(FixedI128::saturating_from_integer(5), Perbill::from_percent(50)),
+			FixedI128::zero()
+		);
+		assert_eq!(
+			ema.update(FixedI128::saturating_from_integer(-4), Perbill::from_percent(75)),
+			FixedI128::saturating_from_integer(-3)
+		);
+		let mut ema = Ema::new(FixedI128::min_value());
+		assert_eq!(ema.update(FixedI128::max_value(), Perbill::one()), FixedI128::max_value());
+		assert_eq!(ema.update(FixedI128::min_value(), Perbill::one()), FixedI128::min_value());
+		let half = ema.update(FixedI128::max_value(), Perbill::from_percent(50));
+		assert_eq!(half, FixedI128::from_inner(-1));
+	}
+
+	#[test]
+	fn saturating_from_other_fixed_point_types_works() {
+		use crate::traits::SaturatingInto;
+
+		// widening keeps every digit.
+		let x = FixedI64::from_inner(-1_234_567_891);
+		assert_eq!(
+			FixedI128::saturating_from(x),
+			FixedI128::from_inner(-1_234_567_891_000_000_000)
+		);
+		let round_trip: FixedI64 =
+			FixedU128::saturating_from(FixedI64::max_value()).saturating_into();
+		assert_eq!(round_trip, FixedI64::max_value());
+		assert_eq!(FixedI128::saturating_from(FixedI128::min_value()), FixedI128::min_value());
+
+		// narrowing rounds to the nearest, with an exact half rounding towards zero.
+		let round = |inner: i128| FixedI64::saturating_from(FixedI128::from_inner(inner));
+		assert_eq!(round(1_500_000_000), FixedI64::from_inner(1));
+		assert_eq!(round(1_500_000_001), FixedI64::from_inner(2));
+		assert_eq!(round(1_499_999_999), FixedI64::from_inner(1));
+		assert_eq!(round(500_000_000), FixedI64::from_inner(0));
+		assert_eq!(round(500_000_001), FixedI64::from_inner(1));
+		assert_eq!(round(-500_000_001), FixedI64::from_inner(-1));
+		assert_eq!(round(-400_000_000), FixedI64::from_inner(0));
+		let x: FixedI64 = FixedU128::saturating_from_rational(1, 3).saturating_into();
+		assert_eq!(x, FixedI64::from_inner(333_333_333));
+
+		// out of range values saturate to the closest bound.
+		assert_eq!(FixedI64::saturating_from(FixedU128::max_value()), FixedI64::max_value());
+		assert_eq!(FixedI64::saturating_from(FixedI128::min_value()), FixedI64::min_value());
+		assert_eq!(FixedU128::saturating_from(FixedI64::from_inner(-1)), FixedU128::zero());
+		assert_eq!(FixedU128::saturating_from(FixedI128::min_value()), FixedU128::zero());
+		assert_eq!(FixedI128::saturating_from(FixedU128::max_value()), FixedI128::max_value());
+		assert_eq!(
+			FixedU128::saturating_from(FixedI128::max_value()),
+			FixedU128::from_inner(i128::MAX as u128)
+		);
+		assert_eq!(
+			FixedI64::saturating_from(FixedU128::saturating_from_integer(9_223_372_036u64)),
+			FixedI64::saturating_from_integer(9_223_372_036i64)
+		);
+	}
+
+	#[test]
+	fn ema_can_be_encoded() {
+		let ema = Ema::new(FixedU128::saturating_from_rational(1, 3));
+		assert_eq!(ema.encode(), ema.average().encode());
+		assert_eq!(Ema::<FixedU128>::decode(&mut &ema.encode()[..]), Ok(ema));
+		assert_eq!(<Ema<FixedI64> as codec::MaxEncodedLen>::max_encoded_len(), 8);
+	}
+
+	#[test]
+	fn fixed_u128_sqrt_works() {
+		assert_eq!(FixedU128::zero().sqrt(), FixedU128::zero());
+		assert_eq!(FixedU128::one().sqrt(), FixedU128::one());
+		assert_eq!(FixedU128::from(16).sqrt(), FixedU128::from(4));
+		assert_eq!(
+			FixedU128::saturating_from_rational(1, 4).sqrt(),
+			FixedU128::saturating_from_rational(1, 2)
+		);
+		assert_eq!(FixedU128::from(2).sqrt(), FixedU128::from_inner(1_414_213_562_373_095_049));
+		assert_eq!(FixedU128::from(3).sqrt(), FixedU128::from_inner(1_732_050_807_568_877_294));
+
+		// The smallest value has a root of `10^-9`.
+		assert_eq!(FixedU128::from_inner(1).sqrt(), FixedU128::from_inner(1_000_000_000));
+		assert_eq!(
+			FixedU128::max_value().sqrt(),
+			FixedU128::from_inner(18_446_744_073_709_551_616_000_000_000)
+		);
+	}
+}
@@ -20,28 +20,46 @@
 //! assumptions of a bigger type (u128) being available, or simply create a per-thing and use the
 //! multiplication implementation provided there.
 
-use crate::biguint;
-use num_traits::Zero;
-use sp_std::{
-	cmp::{max, min},
-	mem,
-};
+use crate::{biguint, PerThing, Perquintill, Rounding};
+use num_traits::{One, Zero};
+use sp_std::{cmp::Ordering, mem, vec::Vec};
 
 /// Helper gcd function used in Rational128 implementation.
-pub fn gcd(a: u128, b: u128) -> u128 {
-	match ((a, b), (a & 1, b & 1)) {
-		((x, y), _) if x == y => y,
-		((0, x), _) | ((x, 0), _) => x,
-		((x, y), (0, 1)) | ((y, x), (1, 0)) => gcd(x >> 1, y),
-		((x, y), (0, 0)) => gcd(x >> 1, y >> 1) << 1,
-		((x, y), (1, 1)) => {
-			let (x, y) = (min(x, y), max(x, y));
-			gcd((y - x) >> 1, x)
-		},
-		_ => unreachable!(),
+///
+/// This is Stein's binary gcd, in a loop rather than recursively so that the stack depth does
+/// not depend on the inputs.
+pub fn gcd(mut a: u128, mut b: u128) -> u128 {
+	if a == 0 || b == 0 {
+		return a | b
+	}
+	// The common factors of two are put back at the end.
+	let shift = (a | b).trailing_zeros();
+	a >>= a.trailing_zeros();
+	loop {
+		// `a` is odd here, so factors of two in `b` are not shared.
+		b >>= b.trailing_zeros();
+		if a > b {
+			mem::swap(&mut a, &mut b);
+		}
+		b -= a;
+		if b == 0 {
+			return a << shift
+		}
 	}
 }
 
+/// Returns the gcd of all `values`, together with each value divided by it.
+///
+/// If the gcd is zero (all values are zero) or one, the values are returned unchanged.
+pub fn reduce_all(values: &[u128]) -> (u128, Vec<u128>) {
+	let g = values.iter().fold(0, |g, v| gcd(g, *v));
+	let reduced = match g {
+		0 | 1 => values.to_vec(),
+		g => values.iter().map(|v| v / g).collect(),
+	};
+	(g, reduced)
+}
+
 /// split a u128 into two u64 limbs
 pub fn split(a: u128) -> (u64, u64) {
 	let al = a as u64;
@@ -49,6 +67,46 @@ pub fn split(a: u128) -> (u64, u64) {
 	(ah, al)
 }
 
+/// Split a u128 into two u64 limbs, least significant first.
+///
+/// For byte-level conversions, `u128::to_le_bytes` and friends are already usable in const
+/// contexts.
+pub const fn to_le_limbs(x: u128) -> [u64; 2] {
+	[x as u64, (x >> 64) as u64]
+}
+
+/// Combine two u64 limbs, least significant first, into a u128. Inverse of [`to_le_limbs`].
+pub const fn from_le_limbs(limbs: [u64; 2]) -> u128 {
+	(limbs[1] as u128) << 64 | limbs[0] as u128
+}
+
+/// Restrict `v` to the inclusive range `[lo, hi]`, like [`Ord::clamp`] but usable in const
+/// contexts.
+///
+/// Panics if `lo > hi`.
+pub const fn clamp_u128(v: u128, lo: u128, hi: u128) -> u128 {
+	assert!(lo <= hi);
+	if v < lo {
+		lo
+	} else if v > hi {
+		hi
+	} else {
+		v
+	}
+}
+
+/// Narrow `x` into a `usize`, returning `None` if it does not fit.
+///
+/// The width of `usize` depends on the target, so the same `x` may fit on a 64-bit target but
+/// not on a 32-bit one (such as wasm32).
+pub const fn try_usize(x: u128) -> Option<usize> {
+	if x > usize::MAX as u128 {
+		None
+	} else {
+		Some(x as usize)
+	}
+}
+
 /// Convert a u128 to a u32 based biguint.
 pub fn to_big_uint(x: u128) -> biguint::BigUint {
 	let (xh, xl) = split(x);
@@ -59,6 +117,34 @@ pub fn to_big_uint(x: u128) -> biguint::BigUint {
 	n
 }
 
+#[cfg(feature = "rational-path-stats")]
+mod path_counters {
+	use std::sync::atomic::AtomicU64;
+
+	pub static FAST: AtomicU64 = AtomicU64::new(0);
+	pub static BIG: AtomicU64 = AtomicU64::new(0);
+}
+
+/// Record which path `multiply_by_rational` took. This is a no-op unless the
+/// `rational-path-stats` feature is enabled.
+#[inline(always)]
+fn record_path(_big: bool) {
+	#[cfg(feature = "rational-path-stats")]
+	{
+		use std::sync::atomic::Ordering;
+		let counter = if _big { &path_counters::BIG } else { &path_counters::FAST };
+		counter.fetch_add(1, Ordering::Relaxed);
+	}
+}
+
+/// Returns how many times `multiply_by_rational` has taken the fast `checked_mul` path and the
+/// big-int path respectively, as `(fast_count, big_count)`.
+#[cfg(feature = "rational-path-stats")]
+pub fn path_stats() -> (u64, u64) {
+	use std::sync::atomic::Ordering;
+	(path_counters::FAST.load(Ordering::Relaxed), path_counters::BIG.load(Ordering::Relaxed))
+}
+
 /// Safely and accurately compute `a * b / c`. The approach is:
 ///   - Simply try `a * b / c`.
 ///   - Else, convert them both into big numbers and re-try. `Err` is returned if the result cannot
@@ -89,8 +175,10 @@ pub fn multiply_by_rational(mut a: u128, mut b: u128, mut c: u128) -> Result<u12
 
 	if let Some(x) = a.checked_mul(b) {
 		// This is the safest way to go. Try it.
+		record_path(false);
 		Ok(x / c)
 	} else {
+		record_path(true);
 		let a_num = to_big_uint(a);
 		let b_num = to_big_uint(b);
 		let c_num = to_big_uint(c);
@@ -117,3 +205,2171 @@ pub fn multiply_by_rational(mut a: u128, mut b: u128, mut c: u128) -> Result<u12
 		q.try_into().map_err(|_| "result cannot fit in u128")
 	}
 }
+
+pub use double128::{Double128, Quad128};
+
+mod double128 {
+	// Inspired by: https://medium.com/wicketh/mathemagic-512-bit-division-in-solidity-afa55870a65
+
+	/// Returns the least significant 64 bits of a
+	const fn low_64(a: u128) -> u128 {
+		a & ((1 << 64) - 1)
+	}
+
+	/// Returns the most significant 64 bits of a
+	const fn high_64(a: u128) -> u128 {
+		a >> 64
+	}
+
+	/// Returns 2^128 - a (two's complement)
+	const fn neg128(a: u128) -> u128 {
+		(!a).wrapping_add(1)
+	}
+
+	/// Returns 2^128 / a
+	const fn div128(a: u128) -> u128 {
+		(neg128(a) / a).wrapping_add(1)
+	}
+
+	/// Returns 2^128 % a
+	const fn mod128(a: u128) -> u128 {
+		neg128(a) % a
+	}
+
+	/// A 256-bit unsigned integer, represented as two 128-bit halves.
+	///
+	/// This is enough to hold the product of two `u128` values, or to accumulate many such
+	/// products before a final division, e.g. with [`Double128::sum_of_products`] and
+	/// [`Double128::div`] or [`Double128::div_double`]. Values are ordered numerically.
+	#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+	pub struct Double128 {
+		high: u128,
+		low: u128,
+	}
+
+	impl From<u128> for Double128 {
+		fn from(low: u128) -> Self {
+			Self::from_low(low)
+		}
+	}
+
+	impl Double128 {
+		/// Narrow `self` into a `u128`, failing if the high half is non-zero.
+		pub const fn try_into_u128(self) -> Option<u128> {
+			match self.high {
+				0 => Some(self.low),
+				_ => None,
+			}
+		}
+
+		/// Split `self` into its `(high, low)` halves.
+		pub const fn into_parts(self) -> (u128, u128) {
+			(self.high, self.low)
+		}
+
+		/// The zero value.
+		pub const fn zero() -> Self {
+			Self { high: 0, low: 0 }
+		}
+
+		/// Return a `Double128` value representing the `scaled_value << 64`.
+		///
+		/// This means the lower half of the `high` component will be equal to the upper 64-bits of
+		/// `scaled_value` (in the lower positions) and the upper half of the `low` component will
+		/// be equal to the lower 64-bits of `scaled_value`.
+		pub const fn left_shift_64(scaled_value: u128) -> Self {
+			Self { high: scaled_value >> 64, low: scaled_value << 64 }
+		}
+
+		/// Construct a value from the lower 128 bits only, with the upper being zeroed.
+		pub const fn from_low(low: u128) -> Self {
+			Self { high: 0, low }
+		}
+
+		/// Returns the same value ignoring anything in the high 128-bits.
+		pub const fn low_part(self) -> Self {
+			Self { high: 0, ..self }
+		}
+
+		/// Returns a*b (in 256 bits)
+		pub const fn product_of(a: u128, b: u128) -> Self {
+			// Split a and b into hi and lo 64-bit parts
+			let (a_low, a_high) = (low_64(a), high_64(a));
+			let (b_low, b_high) = (low_64(b), high_64(b));
+			// a = (a_low + a_high << 64); b = (b_low + b_high << 64);
+			// a * b = (a_low + a_high << 64)(b_low + b_high << 64)
+			// = a_low * b_low
+			// + a_low * b_high << 64
+			// + a_high << 64 * b_low
+			// + a_high << 64 * b_high << 64
+			// assume: f = a_low * b_low
+			// assume: o = a_low * b_high
+			// assume: i = a_high * b_low
+			// assume: l = a_high * b_high
+			// therefore: a * b = l << 128 + o << 64 + i << 64 + f
+			let (f, o, i, l) = (a_low * b_low, a_low * b_high, a_high * b_low, a_high * b_high);
+			let fl = Self { high: l, low: f };
+			let i = Self::left_shift_64(i);
+			let o = Self::left_shift_64(o);
+			fl.add(i).add(o)
+		}
+
+		/// Returns `self + b`, wrapping around on overflow of 256 bits.
+		pub const fn add(self, b: Self) -> Self {
+			let (low, overflow) = self.low.overflowing_add(b.low);
+			let carry = overflow as u128; // 1 if true, 0 if false.
+			let high = self.high.wrapping_add(b.high).wrapping_add(carry);
+			Double128 { high, low }
+		}
+
+		/// Returns `self + b`, or `None` if the sum does not fit in 256 bits.
+		pub const fn checked_add(self, b: Self) -> Option<Self> {
+			let (low, overflow) = self.low.overflowing_add(b.low);
+			match self.high.checked_add(b.high) {
+				Some(high) => match high.checked_add(overflow as u128) {
+					Some(high) => Some(Double128 { high, low }),
+					None => None,
+				},
+				None => None,
+			}
+		}
+
+		/// Returns the sum of `a * b` over all `pairs`, or `None` if it does not fit in 256 bits.
+		///
+		/// Every single product fits, so this can only fail for more than one pair.
+		pub fn sum_of_products(pairs: &[(u128, u128)]) -> Option<Self> {
+			pairs
+				.iter()
+				.try_fold(Self::zero(), |acc, (a, b)| acc.checked_add(Self::product_of(*a, *b)))
+		}
+
+		/// The full decimal expansion of `self`, for debugging.
+		#[cfg(feature = "std")]
+		pub fn to_decimal_string(&self) -> String {
+			let mut digits = Vec::new();
+			let mut rest = *self;
+			loop {
+				let (quotient, digit) = rest.div(10);
+				digits.push(b'0' + digit as u8);
+				rest = quotient;
+				if rest == Self::zero() {
+					break
+				}
+			}
+			digits.reverse();
+			String::from_utf8(digits).expect("only ASCII digits were pushed; qed")
+		}
+
+		/// Returns `self * rhs`, or `None` if the product does not fit in 256 bits.
+		pub const fn checked_mul_u128(self, rhs: u128) -> Option<Self> {
+			match Quad128::product_of_double(self, rhs) {
+				Quad128 { high: Double128 { high: 0, low: 0 }, low } => Some(low),
+				_ => None,
+			}
+		}
+
+		/// Returns `(self / rhs, self % rhs)`.
+		///
+		/// Panics if `rhs` is zero.
+		pub const fn div(mut self, rhs: u128) -> (Self, u128) {
+			if rhs == 1 {
+				return (self, 0)
+			}
+
+			// (self === a; rhs === b)
+			// Calculate a / b
+			// = (a_high << 128 + a_low) / b
+			//   let (q, r) = (div128(b), mod128(b));
+			// = (a_low + a_high * (q * b + r)) / b
+			// = (a_low + a_high * r) / b + a_high * q
+			let (q, r) = (div128(rhs), mod128(rhs));
+
+			// x = current result
+			// a = next number
+			let mut x = Self::zero();
+			while self.high != 0 {
+				// x += a.high * q
+				x = x.add(Self::product_of(self.high, q));
+				// a = a.low + a.high * r
+				self = Self::product_of(self.high, r).add(self.low_part());
+			}
+
+			(x.add(Self::from_low(self.low / rhs)), self.low % rhs)
+		}
+
+		/// Returns `(self / rhs, self % rhs)` for a 256-bit divisor.
+		///
+		/// Divisors that fit in a `u128` are handed to [`Self::div`]; larger ones use long division
+		/// on 64-bit limbs (Knuth's algorithm D), in which case the quotient fits in a `u128`.
+		///
+		/// Panics if `rhs` is zero.
+		pub const fn div_double(self, rhs: Self) -> (Self, Self) {
+			if rhs.high == 0 {
+				let (quotient, remainder) = self.div(rhs.low);
+				return (quotient, Self::from_low(remainder))
+			}
+			if self.high < rhs.high || (self.high == rhs.high && self.low < rhs.low) {
+				return (Self::zero(), self)
+			}
+
+			// Shift both sides so that the top limb of the divisor has its highest bit set. Then
+			// each quotient limb estimated from the top two limbs is at most two too large.
+			let n = if high_64(rhs.high) == 0 { 3 } else { 4 };
+			let shift = rhs.high.leading_zeros() % 64;
+			let v = rhs.shl(shift).to_limbs();
+			let mut u = [0u64; 5];
+			let shifted = self.shl(shift).to_limbs();
+			let mut i = 0;
+			while i < 4 {
+				u[i] = shifted[i];
+				i += 1;
+			}
+			if shift != 0 {
+				u[4] = (self.high >> (128 - shift)) as u64;
+			}
+
+			let mut quotient = 0;
+			let mut j = 5 - n;
+			while j > 0 {
+				j -= 1;
+				// Estimate the quotient limb from the top two limbs, and refine it with the next.
+				let top = (u[j + n] as u128) << 64 | u[j + n - 1] as u128;
+				let mut q = top / v[n - 1] as u128;
+				let mut r = top % v[n - 1] as u128;
+				while high_64(q) != 0 || q * v[n - 2] as u128 > (r << 64 | u[j + n - 2] as u128) {
+					q -= 1;
+					r += v[n - 1] as u128;
+					if high_64(r) != 0 {
+						break
+					}
+				}
+
+				// u -= q * v, shifted by j limbs.
+				let mut borrow: i128 = 0;
+				let mut i = 0;
+				while i < n {
+					let p = q * v[i] as u128;
+					let t = u[i + j] as i128 - borrow - low_64(p) as i128;
+					u[i + j] = t as u64;
+					borrow = high_64(p) as i128 - (t >> 64);
+					i += 1;
+				}
+				let t = u[j + n] as i128 - borrow;
+				u[j + n] = t as u64;
+
+				// Rarely, the estimate is still one too large: add v back.
+				if t < 0 {
+					q -= 1;
+					let mut carry = 0;
+					let mut i = 0;
+					while i < n {
+						let t = u[i + j] as u128 + v[i] as u128 + carry;
+						u[i + j] = t as u64;
+						carry = high_64(t);
+						i += 1;
+					}
+					u[j + n] = u[j + n].wrapping_add(carry as u64);
+				}
+				quotient |= q << (64 * j);
+			}
+
+			let remainder = Self::from_limbs([u[0], u[1], u[2], u[3]]).shr(shift);
+			(Self::from_low(quotient), remainder)
+		}
+
+		/// The 64-bit limbs of `self`, least significant first.
+		const fn to_limbs(self) -> [u64; 4] {
+			[self.low as u64, high_64(self.low) as u64, self.high as u64, high_64(self.high) as u64]
+		}
+
+		/// The value of the 64-bit limbs `l`, least significant first.
+		const fn from_limbs(l: [u64; 4]) -> Self {
+			Self {
+				high: (l[3] as u128) << 64 | l[2] as u128,
+				low: (l[1] as u128) << 64 | l[0] as u128,
+			}
+		}
+
+		/// Returns `self << shift`, dropping any overflowing bits. `shift` must be below 128.
+		const fn shl(self, shift: u32) -> Self {
+			match shift {
+				0 => self,
+				_ => Self {
+					high: self.high << shift | self.low >> (128 - shift),
+					low: self.low << shift,
+				},
+			}
+		}
+
+		/// Returns `self >> shift`. `shift` must be below 128.
+		const fn shr(self, shift: u32) -> Self {
+			match shift {
+				0 => self,
+				_ => Self {
+					high: self.high >> shift,
+					low: self.low >> shift | self.high << (128 - shift),
+				},
+			}
+		}
+	}
+
+	/// A 512-bit unsigned integer, represented as two 256-bit halves.
+	///
+	/// Used to hold the product of three `u128` values.
+	#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+	pub struct Quad128 {
+		high: Double128,
+		low: Double128,
+	}
+
+	impl Quad128 {
+		/// Narrow `self` into a `u128`, failing if any of the upper 384 bits is non-zero.
+		pub const fn try_into_u128(self) -> Option<u128> {
+			match (self.high, self.low.high) {
+				(Double128 { high: 0, low: 0 }, 0) => Some(self.low.low),
+				_ => None,
+			}
+		}
+
+		/// Returns a*b (in 512 bits)
+		pub const fn product_of_double(a: Double128, b: u128) -> Self {
+			// a * b = (a.high * b) << 128 + a.low * b
+			let low = Double128::product_of(a.low, b);
+			let high = Double128::product_of(a.high, b);
+			let (mid, overflow) = low.high.overflowing_add(high.low);
+			// The product is less than 2^384, so this can't overflow.
+			let top = high.high + overflow as u128;
+			Self { high: Double128::from_low(top), low: Double128 { high: mid, low: low.low } }
+		}
+
+		/// Returns `(self / rhs, self % rhs)`.
+		///
+		/// Panics if `rhs` is zero.
+		pub const fn div(self, rhs: u128) -> (Self, u128) {
+			// Long division, one 128-bit limb at a time. The remainder carried into each step is
+			// less than `rhs`, so every partial quotient fits in 128 bits.
+			let limbs = [self.high.high, self.high.low, self.low.high, self.low.low];
+			let mut quotient = [0u128; 4];
+			let mut remainder = 0;
+			let mut i = 0;
+			while i < limbs.len() {
+				let (q, r) = Double128 { high: remainder, low: limbs[i] }.div(rhs);
+				quotient[i] = q.low;
+				remainder = r;
+				i += 1;
+			}
+			let high = Double128 { high: quotient[0], low: quotient[1] };
+			let low = Double128 { high: quotient[2], low: quotient[3] };
+			(Self { high, low }, remainder)
+		}
+	}
+}
+
+/// Reduce the fraction `num / den` by the greatest common divisor of its 256-bit numerator and
+/// `u128` denominator.
+///
+/// The value of the fraction is unchanged, but a reduced numerator may fit in a `u128` when the
+/// original did not, allowing the caller to keep working with plain `u128` fractions. If `den` is
+/// zero, the inputs are returned unchanged.
+pub fn reduce_double128_over_u128(
+	num: double128::Double128,
+	den: u128,
+) -> (double128::Double128, u128) {
+	if den == 0 {
+		return (num, den)
+	}
+	// `gcd(num, den) == gcd(num % den, den)`, and the latter fits in 128 bits.
+	let g = gcd(num.div(den).1, den);
+	(num.div(g).0, den / g)
+}
+
+/// Narrow the quotient of a 256-bit division by `c` into a `u128`, rounding it according to `r`.
+///
+/// `result` and `remainder` are expected to be the outcome of dividing some 256-bit value by `c`.
+/// Returns `None` if `result` or the rounded-up value do not fit in a `u128`.
+pub const fn round_double128_div(
+	result: double128::Double128,
+	remainder: u128,
+	c: u128,
+	r: Rounding,
+) -> Option<u128> {
+	match result.try_into_u128() {
+		Some(result) => round_div(result, remainder, c, r),
+		None => None,
+	}
+}
+
+/// Round the quotient `result` of a division by `c`, given its `remainder`, according to `r`.
+///
+/// Returns `None` if rounding up overflows.
+const fn round_div(result: u128, remainder: u128, c: u128, r: Rounding) -> Option<u128> {
+	if match r {
+		Rounding::Up => remainder > 0,
+		Rounding::Nearest => remainder > c / 2,
+		Rounding::ToEven => remainder > c / 2 || (remainder == c - remainder && result % 2 == 1),
+		Rounding::Down => false,
+	} {
+		result.checked_add(1)
+	} else {
+		Some(result)
+	}
+}
+
+/// Returns the full 256-bit product of `a` and `b`, as its `(high, low)` 128-bit halves.
+pub const fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+	double128::Double128::product_of(a, b).into_parts()
+}
+
+/// Returns `a * b / c` (wrapping to 128 bits) or `None` in the case of
+/// overflow, rounding according to `r`.
+///
+/// If `a` and `b` both fit in a `u64` their product fits in a `u128`, and the much cheaper native
+/// division is used, as in [`multiply_by_rational_u64`]. This matters most on targets such as
+/// wasm32, where the 256-bit arithmetic of the general case is emulated on 32-bit words.
+///
+/// Panics if `c` is zero.
+pub const fn multiply_by_rational_with_rounding(
+	a: u128,
+	b: u128,
+	c: u128,
+	r: Rounding,
+) -> Option<u128> {
+	if c == 0 {
+		panic!("attempt to divide by zero")
+	}
+	if a <= u64::MAX as u128 && b <= u64::MAX as u128 {
+		let ab = a * b;
+		return round_div(ab / c, ab % c, c, r)
+	}
+	multiply_by_rational_wide(a, b, c, r)
+}
+
+/// The general case of [`multiply_by_rational_with_rounding`], using 256-bit arithmetic.
+const fn multiply_by_rational_wide(a: u128, b: u128, c: u128, r: Rounding) -> Option<u128> {
+	use double128::Double128;
+	let (result, remainder) = Double128::product_of(a, b).div(c);
+	round_double128_div(result, remainder, c, r)
+}
+
+/// Returns the quotient and remainder of `a * b / c`, or `None` if the quotient does not fit in a
+/// `u128`.
+///
+/// The remainder is always less than `c`. It is what [`multiply_by_rational_with_rounding`] uses
+/// to round, and lets callers distribute the rounding dust themselves.
+///
+/// Panics if `c` is zero.
+pub const fn multiply_by_rational_with_remainder(
+	a: u128,
+	b: u128,
+	c: u128,
+) -> Option<(u128, u128)> {
+	use double128::Double128;
+	if c == 0 {
+		panic!("attempt to divide by zero")
+	}
+	let (result, remainder) = Double128::product_of(a, b).div(c);
+	match result.try_into_u128() {
+		Some(result) => Some((result, remainder)),
+		None => None,
+	}
+}
+
+/// Returns `a * b / c` or `None` in the case of overflow, rounding up with probability
+/// `remainder / c` and down otherwise.
+///
+/// The choice is made by comparing `entropy` against the remainder, so for an `entropy` that is
+/// uniformly distributed over all `u128` values the expected result is exactly `a * b / c`, and
+/// repeated rounding is not biased in either direction. Use [`u128::from_le_bytes`] to build it
+/// from a random seed. The result is deterministic for a given `entropy`.
+///
+/// Panics if `c` is zero.
+pub const fn multiply_by_rational_with_stochastic_rounding(
+	a: u128,
+	b: u128,
+	c: u128,
+	entropy: u128,
+) -> Option<u128> {
+	let (result, remainder) = match multiply_by_rational_with_remainder(a, b, c) {
+		Some(qr) => qr,
+		None => return None,
+	};
+	// Round up iff `entropy / 2^128 < remainder / c`, i.e. `entropy * c < remainder * 2^128`. The
+	// latter has no low half, so comparing the high halves is enough.
+	if mul_wide(entropy, c).0 < remainder {
+		result.checked_add(1)
+	} else {
+		Some(result)
+	}
+}
+
+/// Error of a checked rational multiplication.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RationalError {
+	/// The denominator was zero.
+	DivByZero,
+	/// The result does not fit in the output type.
+	Overflow,
+}
+
+/// Returns `a * b / c` for signed operands, rounding according to `r`.
+///
+/// Rounding is relative to the real value of the result, so `Down` rounds towards negative
+/// infinity, `Up` towards positive infinity and `Nearest` rounds an exact half towards negative
+/// infinity. `ToEven` is symmetric, it rounds an exact half to the even neighbour either way.
+pub fn multiply_by_rational_signed(
+	a: i128,
+	b: i128,
+	c: i128,
+	r: Rounding,
+) -> Result<i128, RationalError> {
+	// Rounding the magnitude of a negative value up moves it towards negative infinity.
+	signed_multiply_by_rational(a, b, c, |negative, remainder, c| match (negative, r) {
+		(false, r) | (true, r @ Rounding::ToEven) => r,
+		(true, Rounding::Up) => Rounding::Down,
+		(true, Rounding::Down) => Rounding::Up,
+		(true, Rounding::Nearest) if remainder >= c - c / 2 => Rounding::Up,
+		(true, Rounding::Nearest) => Rounding::Down,
+	})
+}
+
+/// Rounding of a signed value, relative to zero.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SignedRounding {
+	/// Round towards zero, i.e. truncate.
+	TowardZero,
+	/// Round away from zero.
+	AwayFromZero,
+	/// Round to the nearest whole number, an exact half is rounded towards zero.
+	Nearest,
+}
+
+/// Returns `a * b / c` for signed operands, rounding according to `r`.
+///
+/// Unlike [`multiply_by_rational_signed`], rounding is relative to zero, so it is symmetric for
+/// positive and negative results.
+pub fn multiply_by_rational_signed_with(
+	a: i128,
+	b: i128,
+	c: i128,
+	r: SignedRounding,
+) -> Result<i128, RationalError> {
+	signed_multiply_by_rational(a, b, c, |_, _, _| match r {
+		SignedRounding::TowardZero => Rounding::Down,
+		SignedRounding::AwayFromZero => Rounding::Up,
+		SignedRounding::Nearest => Rounding::Nearest,
+	})
+}
+
+/// Returns `a * b / c`, where `magnitude_rounding` picks how to round the magnitude of the result
+/// given whether it is negative, the remainder of the division and the magnitude of `c`.
+fn signed_multiply_by_rational(
+	a: i128,
+	b: i128,
+	c: i128,
+	magnitude_rounding: impl FnOnce(bool, u128, u128) -> Rounding,
+) -> Result<i128, RationalError> {
+	use double128::Double128;
+	if c == 0 {
+		return Err(RationalError::DivByZero)
+	}
+	let negative = a != 0 && b != 0 && ((a < 0) ^ (b < 0) ^ (c < 0));
+	let c_abs = c.unsigned_abs();
+	let (result, remainder) = Double128::product_of(a.unsigned_abs(), b.unsigned_abs()).div(c_abs);
+	let r = magnitude_rounding(negative, remainder, c_abs);
+	let magnitude =
+		round_double128_div(result, remainder, c_abs, r).ok_or(RationalError::Overflow)?;
+	if !negative {
+		i128::try_from(magnitude).map_err(|_| RationalError::Overflow)
+	} else if magnitude <= i128::MIN.unsigned_abs() {
+		Ok((magnitude as i128).wrapping_neg())
+	} else {
+		Err(RationalError::Overflow)
+	}
+}
+
+/// Evaluate the fraction `num / den`, rounding according to `r`.
+///
+/// Returns `None` if `den` is zero.
+pub fn rational_to_u128(num: u128, den: u128, r: Rounding) -> Option<u128> {
+	if den == 0 {
+		return None
+	}
+	let (q, rem) = (num / den, num % den);
+	// A non-zero remainder means `den >= 2`, so `q <= u128::MAX / 2` and rounding up can't
+	// overflow.
+	Some(match r {
+		Rounding::Up if rem > 0 => q + 1,
+		Rounding::Nearest if rem > den / 2 => q + 1,
+		Rounding::ToEven if rem > den / 2 || (rem == den - rem && q % 2 == 1) => q + 1,
+		_ => q,
+	})
+}
+
+/// Construct a `PerThing` from the ratio `a / b`, rounding to the nearest part and saturating.
+///
+/// Returns `P::zero()` if `b` is zero and `P::one()` if `a >= b`.
+pub fn per_thing_from_rational_saturating<P: PerThing>(a: u128, b: u128) -> P {
+	if b == 0 {
+		return P::zero()
+	}
+	if a >= b {
+		return P::one()
+	}
+	let accuracy: u128 = P::ACCURACY.into();
+	// `a < b`, so the result is at most `accuracy` and always fits.
+	let parts =
+		multiply_by_rational_with_rounding(a, accuracy, b, Rounding::Nearest).unwrap_or(accuracy);
+	P::from_parts(parts.try_into().unwrap_or(P::ACCURACY))
+}
+
+/// Returns `floor(sqrt(n))`. See [`biguint::BigUint::sqrt`].
+pub fn biguint_sqrt(n: &biguint::BigUint) -> biguint::BigUint {
+	n.sqrt().0
+}
+
+/// Returns the exact value of `a^2 + b^2`.
+pub fn sum_of_squares(a: u128, b: u128) -> biguint::BigUint {
+	let (a, b) = (to_big_uint(a), to_big_uint(b));
+	let mut sum = a.clone().mul(&a).add(&b.clone().mul(&b));
+	sum.lstrip();
+	sum
+}
+
+/// Returns `floor(sqrt(a^2 + b^2))`, or `None` if it does not fit in a `u128`.
+pub fn hypot_floor(a: u128, b: u128) -> Option<u128> {
+	biguint_sqrt(&sum_of_squares(a, b)).try_into().ok()
+}
+
+/// Returns `sqrt(a * b)`, rounded according to `r`.
+///
+/// The root never exceeds `max(a, b)`, even when rounded up, so it always fits in a `u128`.
+pub fn sqrt_of_product(a: u128, b: u128, r: Rounding) -> u128 {
+	let product = to_big_uint(a).mul(&to_big_uint(b));
+	let root = biguint_sqrt(&product);
+	let square = root.clone().mul(&root);
+	let round_up = match r {
+		Rounding::Up => square < product,
+		// `(root + 1/2)^2 == root^2 + root + 1/4`, so the exact root is above the midpoint iff
+		// the integer `product` exceeds `root^2 + root`. It is never exactly on the midpoint.
+		Rounding::Nearest | Rounding::ToEven => square.add(&root) < product,
+		Rounding::Down => false,
+	};
+	let root: u128 = root.try_into().unwrap_or(u128::MAX);
+	root + round_up as u128
+}
+
+/// Returns `floor(log2(x))`, i.e. the position of the highest set bit, or `None` if `x` is zero.
+pub const fn log2_floor(x: u128) -> Option<u32> {
+	if x == 0 {
+		return None
+	}
+	Some(127 - x.leading_zeros())
+}
+
+/// Returns `floor(log10(x))`, i.e. the number of decimal digits of `x` minus one, or `None` if
+/// `x` is zero.
+pub const fn log10_floor(mut x: u128) -> Option<u32> {
+	if x == 0 {
+		return None
+	}
+	let mut log = 0;
+	while x >= 10 {
+		x /= 10;
+		log += 1;
+	}
+	Some(log)
+}
+
+/// Rescale the fraction `num / den` to the denominator `target_den`, returning the new
+/// numerator `num * target_den / den` rounded according to `r`.
+///
+/// Returns `None` if `den` is zero or the new numerator does not fit in a `u128`.
+pub fn rescale(num: u128, den: u128, target_den: u128, r: Rounding) -> Option<u128> {
+	if den == 0 {
+		return None
+	}
+	multiply_by_rational_with_rounding(num, target_den, den, r)
+}
+
+/// Interpolate linearly between `a` and `b`, returning `a + (b - a) * t_num / t_den` rounded
+/// according to `r`.
+///
+/// Works for `a > b` as well, with rounding relative to the real value of the result, so `Down`
+/// always rounds towards zero and `Up` towards infinity. A `t_num` greater than `t_den` is
+/// clamped to `b`. Returns `None` if `t_den` is zero.
+pub fn lerp(a: u128, b: u128, t_num: u128, t_den: u128, r: Rounding) -> Option<u128> {
+	use double128::Double128;
+	if t_den == 0 {
+		return None
+	}
+	if t_num >= t_den {
+		return Some(b)
+	}
+	if a <= b {
+		// `t < 1`, so the delta is less than `b - a` and the sum can't overflow.
+		return multiply_by_rational_with_rounding(b - a, t_num, t_den, r).map(|delta| a + delta)
+	}
+	// The delta is subtracted, so rounding it up rounds the result down.
+	let (delta, remainder) = Double128::product_of(a - b, t_num).div(t_den);
+	let r = match r {
+		Rounding::Up => Rounding::Down,
+		Rounding::Down => Rounding::Up,
+		// On an exact half, the result is even if the delta has the same parity as `a`.
+		Rounding::ToEven if remainder == t_den - remainder =>
+			if (delta.into_parts().1 ^ a) % 2 == 1 {
+				Rounding::Up
+			} else {
+				Rounding::Down
+			},
+		Rounding::Nearest | Rounding::ToEven if remainder >= t_den - t_den / 2 => Rounding::Up,
+		Rounding::Nearest | Rounding::ToEven => Rounding::Down,
+	};
+	round_double128_div(delta, remainder, t_den, r).map(|delta| a - delta)
+}
+
+/// Returns `a * b / c` for 64-bit operands, rounding according to `r` and saturating at
+/// `u64::MAX`.
+///
+/// The product of two `u64` values always fits in a `u128`, so this skips the wide arithmetic of
+/// [`multiply_by_rational_with_rounding`].
+///
+/// Panics if `c` is zero.
+pub const fn multiply_by_rational_u64(a: u64, b: u64, c: u64, r: Rounding) -> u64 {
+	if c == 0 {
+		panic!("attempt to divide by zero")
+	}
+	let (ab, c) = (a as u128 * b as u128, c as u128);
+	// `ab / c` is at most `u64::MAX * u64::MAX`, so rounding it up can't overflow.
+	match round_div(ab / c, ab % c, c, r) {
+		Some(result) if result <= u64::MAX as u128 => result as u64,
+		_ => u64::MAX,
+	}
+}
+
+/// Divide `a` by `b`, rounding the quotient according to `r`.
+///
+/// Panics if `b` is zero.
+pub fn biguint_div_rounded(
+	a: &biguint::BigUint,
+	b: &biguint::BigUint,
+	r: Rounding,
+) -> biguint::BigUint {
+	let mut a = a.clone();
+	let mut b = b.clone();
+	a.lstrip();
+	b.lstrip();
+	if b.is_zero() {
+		panic!("attempt to divide a BigUint by zero")
+	}
+
+	let mut q = if a < b {
+		Zero::zero()
+	} else if b.len() == 1 {
+		a.clone().div_unit(b.lsb())
+	} else if a.len() > b.len() {
+		// PROOF: both are stripped, `b` has at least two limbs and `a` is longer than `b`.
+		a.clone().div(&b, false).map(|(q, _)| q).unwrap_or_else(Zero::zero)
+	} else {
+		// `div` needs the dividend to be longer than the divisor. Divide `a * B` instead and drop
+		// the least significant limb of the quotient.
+		let mut limbs = a.digits.clone();
+		limbs.push(0);
+		let (q, _) = biguint::BigUint::from_limbs(&limbs)
+			.div(&b, false)
+			.unwrap_or_else(|| (Zero::zero(), Zero::zero()));
+		biguint::BigUint::from_limbs(&q.digits[..q.len() - 1])
+	};
+
+	// PROOF: `q * b <= a`, the subtraction can't underflow.
+	let rem = a.sub(&q.clone().mul(&b)).unwrap_or_else(|_| Zero::zero());
+	let round_up = match r {
+		Rounding::Up => !rem.is_zero(),
+		Rounding::Nearest => rem > b.div_unit(2),
+		Rounding::ToEven => {
+			let twice = rem.clone().add(&rem);
+			twice > b || (twice == b && q.checked_get(0).unwrap_or(0) % 2 == 1)
+		},
+		Rounding::Down => false,
+	};
+	if round_up {
+		q = q.add(&One::one());
+	}
+	q.lstrip();
+	q
+}
+
+/// Compute `n!` exactly.
+pub fn factorial_big(n: u32) -> biguint::BigUint {
+	let mut acc: biguint::BigUint = One::one();
+	for i in 2..=n {
+		acc = acc.mul(&i.into());
+		acc.lstrip();
+	}
+	acc
+}
+
+/// Compute the binomial coefficient `n choose k` exactly, which is zero if `k > n`.
+///
+/// This uses the multiplicative formula, where every intermediate value is itself a binomial
+/// coefficient, to keep intermediates small.
+pub fn binomial_big(n: u32, k: u32) -> biguint::BigUint {
+	if k > n {
+		return Zero::zero()
+	}
+	let k = k.min(n - k);
+	let mut acc: biguint::BigUint = One::one();
+	for i in 1..=k {
+		// `acc * (n - k + i)` is always divisible by `i`.
+		acc = acc.mul(&(n - k + i).into()).div_unit(i);
+		acc.lstrip();
+	}
+	acc
+}
+
+/// Format `raw / 10^decimals` as a decimal string with exactly `decimals` fractional digits.
+///
+/// E.g. `format_decimals(1_500_000, 6)` is `"1.500000"`. If `decimals` is zero, only the integer
+/// is returned.
+#[cfg(feature = "std")]
+pub fn format_decimals(raw: u128, decimals: u32) -> String {
+	if decimals == 0 {
+		return raw.to_string()
+	}
+	let width = decimals as usize;
+	match 10u128.checked_pow(decimals) {
+		Some(unit) => format!("{}.{:0>width$}", raw / unit, raw % unit, width = width),
+		// `raw` is always less than one unit.
+		None => format!("0.{:0>width$}", raw, width = width),
+	}
+}
+
+/// Narrow `n` into a `u128`, returning an error that names `context` if it does not fit.
+#[cfg(feature = "std")]
+pub fn ensure_fits_u128(n: &biguint::BigUint, context: &'static str) -> Result<u128, String> {
+	let mut n = n.clone();
+	n.lstrip();
+	n.try_into().map_err(|_| format!("{}: value exceeds u128", context))
+}
+
+/// Returns `a * b / c`, rounding according to `r` and saturating at `u128::max_value()` if the
+/// rounded result overflows.
+///
+/// Panics if `c` is zero.
+pub const fn saturating_multiply_by_rational_with_rounding(
+	a: u128,
+	b: u128,
+	c: u128,
+	r: Rounding,
+) -> u128 {
+	match multiply_by_rational_with_rounding(a, b, c, r) {
+		Some(v) => v,
+		None => u128::max_value(),
+	}
+}
+
+/// Compound `principal` by the ratio `num / den` over `periods` periods, i.e. compute
+/// `principal * (num / den)^periods`, rounding each period according to `r`.
+///
+/// Returns `None` if `den` is zero or any intermediate result overflows.
+pub fn compound(principal: u128, num: u128, den: u128, periods: u32, r: Rounding) -> Option<u128> {
+	if den == 0 {
+		return None
+	}
+	(0..periods).try_fold(principal, |acc, _| multiply_by_rational_with_rounding(acc, num, den, r))
+}
+
+/// Returns the rate accrued by compounding `rate` over `periods` periods, i.e.
+/// `(1 + rate)^periods - 1`, saturating at one.
+///
+/// This takes `O(log(periods))` multiplications by squaring, rather than one per period as in
+/// [`compound`], so a pallet can accrue interest for any number of blocks at once. Each squaring
+/// doubles the relative error of the intermediate values, so they are binary fixed point numbers
+/// with 126 fractional bits, 66 more than a `Perquintill` needs. Even after the 31 squarings for
+/// `u32::MAX` periods the accumulated error stays far below one part, so the result is off by at
+/// most one part from the exact value.
+pub fn compound_rate(rate: Perquintill, periods: u32) -> Perquintill {
+	const ONE: u128 = 1 << 126;
+	// Growth factors are at least one, so once one exceeds two the accrued rate saturates.
+	const TWO: u128 = 2 * ONE;
+	let accuracy = Perquintill::ACCURACY as u128;
+	let mul = |a: u128, b: u128| {
+		// Both factors are at most two, so only a product of exactly four does not fit, and that
+		// saturates anyway.
+		multiply_by_rational_with_rounding(a, b, ONE, Rounding::Nearest).unwrap_or(u128::MAX)
+	};
+
+	let rate = multiply_by_rational_with_rounding(
+		rate.deconstruct().into(),
+		ONE,
+		accuracy,
+		Rounding::Nearest,
+	)
+	.unwrap_or(ONE);
+	let (mut base, mut exp, mut factor) = (ONE + rate, periods, ONE);
+	while exp > 0 {
+		if exp % 2 == 1 {
+			factor = mul(factor, base);
+			if factor > TWO {
+				return Perquintill::one()
+			}
+		}
+		exp /= 2;
+		if exp > 0 {
+			// `base` is still needed for a higher bit, so the final factor is at least as large.
+			base = mul(base, base);
+			if base > TWO {
+				return Perquintill::one()
+			}
+		}
+	}
+	let parts = multiply_by_rational_with_rounding(factor - ONE, accuracy, ONE, Rounding::Nearest)
+		.unwrap_or(accuracy);
+	Perquintill::from_parts(parts.min(accuracy) as u64)
+}
+
+/// Returns the weighted median of `pairs` of `(value, weight)`.
+///
+/// This is the smallest value at which the cumulative weight of the values sorted in ascending
+/// order reaches at least half of the total weight. If the weight splits evenly between two
+/// values, the lower one is returned. Returns `None` if `pairs` is empty or the total weight is
+/// zero.
+pub fn weighted_median(pairs: &[(u128, u128)]) -> Option<u128> {
+	use double128::Double128;
+	let mut sorted = pairs.to_vec();
+	sorted.sort_unstable_by_key(|(value, _)| *value);
+	// Weights are summed in 256 bits so that they can't overflow.
+	let total = sorted
+		.iter()
+		.fold(Double128::zero(), |acc, (_, w)| acc.add(Double128::from_low(*w)));
+	if total == Double128::zero() {
+		return None
+	}
+	let mut cumulative = Double128::zero();
+	for (value, weight) in sorted {
+		cumulative = cumulative.add(Double128::from_low(weight));
+		if weight > 0 && cumulative.add(cumulative) >= total {
+			return Some(value)
+		}
+	}
+	None
+}
+
+/// Returns the mean of the values in `items` of `(value, weight)`, weighted by their weight and
+/// rounded down.
+///
+/// Returns `None` if the total weight is zero, including when `items` is empty, or if it does not
+/// fit in a `u128`.
+pub fn weighted_mean(items: &[(u128, u128)]) -> Option<u128> {
+	use double128::Double128;
+	let mut weighted_sum = Double128::zero();
+	let mut total_weight: u128 = 0;
+	for (value, weight) in items {
+		total_weight = total_weight.checked_add(*weight)?;
+		// The sum is at most `max(value) * total_weight`, which fits in 256 bits as long as
+		// `total_weight` fits in a `u128`.
+		weighted_sum = weighted_sum.add(Double128::product_of(*value, *weight));
+	}
+	if total_weight == 0 {
+		return None
+	}
+	// The mean is at most the largest value, so it fits in a `u128`.
+	weighted_sum.div(total_weight).0.try_into_u128()
+}
+
+/// Compare the fractions `a / b` and `c / d` without overflowing, by comparing `a * d` with `c * b`
+/// in 256 bits.
+///
+/// The denominators are expected to be non-zero.
+pub fn cmp_rationals(a: u128, b: u128, c: u128, d: u128) -> Ordering {
+	use double128::Double128;
+	Double128::product_of(a, d).cmp(&Double128::product_of(c, b))
+}
+
+/// Clamp the fraction `a / b` into the inclusive range `[lo.0 / lo.1, hi.0 / hi.1]`.
+///
+/// Returns the bound the fraction exceeds, or the fraction itself if it is in range. Returns an
+/// error if any of the denominators is zero.
+pub fn clamp_ratio(
+	a: u128,
+	b: u128,
+	lo: (u128, u128),
+	hi: (u128, u128),
+) -> Result<(u128, u128), &'static str> {
+	if b == 0 || lo.1 == 0 || hi.1 == 0 {
+		return Err("denominator cannot be zero")
+	}
+	if cmp_rationals(a, b, lo.0, lo.1) == Ordering::Less {
+		Ok(lo)
+	} else if cmp_rationals(a, b, hi.0, hi.1) == Ordering::Greater {
+		Ok(hi)
+	} else {
+		Ok((a, b))
+	}
+}
+
+/// Compute `(a * b) % c` without overflowing, using a 256-bit intermediate product.
+///
+/// Panics if `c` is zero.
+pub const fn mul_mod(a: u128, b: u128, c: u128) -> u128 {
+	use double128::Double128;
+	if c == 0 {
+		panic!("attempt to calculate the remainder with a divisor of zero")
+	}
+	Double128::product_of(a, b).div(c).1
+}
+
+/// Compute `base^exp mod modulus` without overflowing, using square-and-multiply with 256-bit
+/// intermediate products.
+///
+/// Panics if `modulus` is zero.
+pub fn pow_mod(base: u128, mut exp: u128, modulus: u128) -> u128 {
+	if modulus == 0 {
+		panic!("attempt to calculate the remainder with a divisor of zero")
+	}
+
+	let mut result = 1 % modulus;
+	let mut base = base % modulus;
+	while exp > 0 {
+		if exp & 1 == 1 {
+			result = mul_mod(result, base, modulus);
+		}
+		base = mul_mod(base, base, modulus);
+		exp >>= 1;
+	}
+	result
+}
+
+/// Compute the inverse of `a` modulo `modulus`, i.e. the `x < modulus` with
+/// `a * x % modulus == 1 % modulus`.
+///
+/// Returns `None` if `modulus` is zero or `a` and `modulus` are not coprime.
+pub fn mod_inverse(a: u128, modulus: u128) -> Option<u128> {
+	if modulus == 0 || gcd(a, modulus) != 1 {
+		return None
+	}
+	// Extended Euclidean algorithm, keeping only the coefficients of `a`. They are kept reduced
+	// modulo `modulus` rather than as signed integers, so that they can't overflow.
+	let (mut old_r, mut r) = (a % modulus, modulus);
+	let (mut old_s, mut s) = (1 % modulus, 0);
+	while r != 0 {
+		let q = old_r / r;
+		let next_r = old_r - q * r;
+		old_r = mem::replace(&mut r, next_r);
+		// `old_s - q * s`, modulo `modulus`.
+		let qs = mul_mod(q, s, modulus);
+		let next_s = if old_s >= qs { old_s - qs } else { modulus - (qs - old_s) };
+		old_s = mem::replace(&mut s, next_s);
+	}
+	Some(old_s)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::Rounding::*;
+	use double128::Double128;
+
+	const MAX: u128 = u128::max_value();
+
+	#[test]
+	fn rational_multiply_basic_rounding_works() {
+		assert_eq!(multiply_by_rational_with_rounding(1, 1, 1, Up), Some(1));
+		assert_eq!(multiply_by_rational_with_rounding(3, 1, 3, Up), Some(1));
+		assert_eq!(multiply_by_rational_with_rounding(1, 1, 3, Up), Some(1));
+		assert_eq!(multiply_by_rational_with_rounding(1, 2, 3, Down), Some(0));
+		assert_eq!(multiply_by_rational_with_rounding(1, 1, 3, Nearest), Some(0));
+		assert_eq!(multiply_by_rational_with_rounding(1, 1, 2, Nearest), Some(0));
+		assert_eq!(multiply_by_rational_with_rounding(1, 2, 3, Nearest), Some(1));
+		assert_eq!(multiply_by_rational_with_rounding(MAX, MAX, MAX, Down), Some(MAX));
+		assert_eq!(multiply_by_rational_with_rounding(MAX, MAX, MAX - 1, Down), None);
+	}
+
+	#[test]
+	fn rational_multiply_big_path_matches_biguint() {
+		let cases = [
+			(MAX, MAX / 3, MAX / 2),
+			(MAX - 7, MAX / 5 + 11, MAX - 1),
+			(1 << 100, 1 << 90, (1 << 64) + 3),
+		];
+		for (a, b, c) in cases {
+			let mut ab = to_big_uint(a).mul(&to_big_uint(b));
+			ab.lstrip();
+			let (mut q, mut r) = ab.div(&to_big_uint(c), true).unwrap();
+			q.lstrip();
+			r.lstrip();
+			let q: u128 = q.try_into().unwrap();
+			let r: u128 = r.try_into().unwrap();
+			assert_eq!(multiply_by_rational_with_rounding(a, b, c, Down), Some(q));
+			assert_eq!(multiply_by_rational_with_rounding(a, b, c, Up), Some(q + (r > 0) as u128));
+			assert_eq!(
+				multiply_by_rational_with_rounding(a, b, c, Nearest),
+				Some(q + (r > c / 2) as u128)
+			);
+			assert_eq!(
+				multiply_by_rational_with_rounding(a, b, c, ToEven),
+				Some(q + (r > c / 2) as u128)
+			);
+		}
+	}
+
+	#[test]
+	fn to_even_rounding_works() {
+		assert_eq!(multiply_by_rational_with_rounding(1, 1, 2, ToEven), Some(0));
+		assert_eq!(multiply_by_rational_with_rounding(3, 1, 2, ToEven), Some(2));
+		assert_eq!(multiply_by_rational_with_rounding(5, 1, 2, ToEven), Some(2));
+		assert_eq!(multiply_by_rational_with_rounding(7, 1, 2, ToEven), Some(4));
+		assert_eq!(multiply_by_rational_with_rounding(4, 1, 3, ToEven), Some(1));
+		assert_eq!(multiply_by_rational_with_rounding(5, 1, 3, ToEven), Some(2));
+		assert_eq!(multiply_by_rational_with_rounding(MAX, 1, 2, ToEven), Some(MAX / 2 + 1));
+		assert_eq!(multiply_by_rational_with_rounding(MAX, 3, 6, ToEven), Some(MAX / 2 + 1));
+
+		let q = Double128::from_low(10);
+		assert_eq!(round_double128_div(q, 2, 4, ToEven), Some(10));
+		assert_eq!(round_double128_div(q, 3, 4, ToEven), Some(11));
+		assert_eq!(round_double128_div(Double128::from_low(11), 2, 4, ToEven), Some(12));
+		assert_eq!(round_double128_div(Double128::from_low(MAX), 1, 2, ToEven), None);
+
+		// Ties are rounded to even on both sides of zero.
+		assert_eq!(multiply_by_rational_signed(5, 1, 2, ToEven), Ok(2));
+		assert_eq!(multiply_by_rational_signed(-5, 1, 2, ToEven), Ok(-2));
+		assert_eq!(multiply_by_rational_signed(-3, 1, 2, ToEven), Ok(-2));
+		assert_eq!(multiply_by_rational_signed(7, 1, -2, ToEven), Ok(-4));
+		assert_eq!(multiply_by_rational_signed(-5, 1, 3, ToEven), Ok(-2));
+
+		assert_eq!(rational_to_u128(5, 2, ToEven), Some(2));
+		assert_eq!(rational_to_u128(7, 2, ToEven), Some(4));
+		assert_eq!(rational_to_u128(7, 3, ToEven), Some(2));
+
+		assert_eq!(multiply_by_rational_u64(5, 1, 2, ToEven), 2);
+		assert_eq!(multiply_by_rational_u64(3, 1, 2, ToEven), 2);
+		assert_eq!(multiply_by_rational_u64(u64::MAX, 1, 2, ToEven), u64::MAX / 2 + 1);
+
+		assert_eq!(lerp(10, 20, 1, 4, ToEven), Some(12));
+		assert_eq!(lerp(10, 21, 1, 2, ToEven), Some(16));
+		assert_eq!(lerp(20, 10, 1, 4, ToEven), Some(18));
+		assert_eq!(lerp(20, 10, 3, 4, ToEven), Some(12));
+		assert_eq!(lerp(21, 10, 1, 2, ToEven), Some(16));
+		assert_eq!(lerp(21, 0, 1, 2, ToEven), Some(10));
+		assert_eq!(lerp(20, 10, 1, 3, ToEven), Some(17));
+
+		assert_eq!(sqrt_of_product(2, 2, ToEven), 2);
+		assert_eq!(sqrt_of_product(2, 1, ToEven), 1);
+		assert_eq!(sqrt_of_product(3, 1, ToEven), 2);
+
+		let div = |a: u128, b: u128| -> u128 {
+			biguint_div_rounded(&to_big_uint(a), &to_big_uint(b), ToEven)
+				.try_into()
+				.unwrap()
+		};
+		assert_eq!(div(5, 2), 2);
+		assert_eq!(div(7, 2), 4);
+		assert_eq!(div(8, 3), 3);
+		assert_eq!(div(MAX, 2), MAX / 2 + 1);
+		let b = (1 << 65) + 2;
+		assert_eq!(div(5 * b + b / 2, b), 6);
+		assert_eq!(div(6 * b + b / 2, b), 6);
+		assert_eq!(div(6 * b + b / 2 + 1, b), 7);
+	}
+
+	#[test]
+	fn round_double128_div_works() {
+		let q = Double128::from_low(10);
+		assert_eq!(round_double128_div(q, 0, 4, Up), Some(10));
+		assert_eq!(round_double128_div(q, 1, 4, Up), Some(11));
+		assert_eq!(round_double128_div(q, 3, 4, Down), Some(10));
+		assert_eq!(round_double128_div(q, 2, 4, Nearest), Some(10));
+		assert_eq!(round_double128_div(q, 3, 4, Nearest), Some(11));
+		assert_eq!(round_double128_div(q, 2, 3, Nearest), Some(11));
+	}
+
+	#[test]
+	fn round_double128_div_carry_overflow() {
+		let q = Double128::from_low(MAX);
+		assert_eq!(round_double128_div(q, 0, 2, Up), Some(MAX));
+		assert_eq!(round_double128_div(q, 1, 2, Down), Some(MAX));
+		assert_eq!(round_double128_div(q, 1, 2, Up), None);
+		assert_eq!(round_double128_div(q, 2, 3, Nearest), None);
+		assert_eq!(round_double128_div(Double128::left_shift_64(1 << 64), 0, 2, Down), None);
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn format_decimals_works() {
+		assert_eq!(format_decimals(1_500_000, 6), "1.500000");
+		assert_eq!(format_decimals(1_234_560_000, 6), "1234.560000");
+		assert_eq!(format_decimals(42, 6), "0.000042");
+		assert_eq!(format_decimals(0, 3), "0.000");
+		assert_eq!(format_decimals(1234, 0), "1234");
+		assert_eq!(format_decimals(MAX, 38), "3.40282366920938463463374607431768211455");
+		assert_eq!(format_decimals(12, 40), format!("0.{}12", "0".repeat(38)));
+	}
+
+	#[test]
+	fn biguint_div_rounded_matches_rational_multiply() {
+		let cases = [
+			(0, 7),
+			(5, 7),
+			(100, 7),
+			(100, 8),
+			(5, 2),
+			(7, 2),
+			(MAX, 2),
+			(MAX, 3),
+			(MAX, MAX / 2),
+			(MAX - 1, MAX),
+			(MAX, (1 << 64) + 1),
+			((1 << 64) + 7, (1 << 64) + 3),
+			((1 << 100) + 12345, 1 << 99),
+		];
+		for (a, b) in cases {
+			for r in [Up, Down, Nearest, ToEven] {
+				let q = biguint_div_rounded(&to_big_uint(a), &to_big_uint(b), r);
+				assert_eq!(
+					q.try_into().ok(),
+					multiply_by_rational_with_rounding(a, 1, b, r),
+					"{} / {} with {:?}",
+					a,
+					b,
+					r,
+				);
+			}
+		}
+	}
+
+	#[test]
+	#[should_panic(expected = "attempt to divide a BigUint by zero")]
+	fn biguint_div_rounded_by_zero_panics() {
+		biguint_div_rounded(&to_big_uint(1), &to_big_uint(0), Down);
+	}
+
+	fn pow_mod_reference(base: u128, exp: u32, modulus: u128) -> u128 {
+		let m = to_big_uint(modulus);
+		let mut acc = to_big_uint(1);
+		for _ in 0..exp {
+			acc = acc.mul(&to_big_uint(base));
+			acc.lstrip();
+			let q = biguint_div_rounded(&acc, &m, Down);
+			acc = acc.sub(&q.mul(&m)).unwrap();
+			acc.lstrip();
+		}
+		let q = biguint_div_rounded(&acc, &m, Down);
+		let mut acc = acc.sub(&q.mul(&m)).unwrap();
+		acc.lstrip();
+		acc.try_into().unwrap()
+	}
+
+	#[test]
+	fn pow_mod_works() {
+		// 2^127 - 1 is prime.
+		const M127: u128 = (1 << 127) - 1;
+
+		assert_eq!(pow_mod(4, 13, 497), 445);
+		assert_eq!(pow_mod(0, 0, 7), 1);
+		assert_eq!(pow_mod(123, 0, 1), 0);
+		assert_eq!(pow_mod(MAX, MAX, 1), 0);
+		assert_eq!(pow_mod(2, 127, M127), 1);
+		assert_eq!(pow_mod(MAX, MAX, MAX), 0);
+		assert_eq!(pow_mod(MAX, 2, MAX - 1), 1);
+		// Fermat's little theorem.
+		assert_eq!(pow_mod(3, M127 - 1, M127), 1);
+		assert_eq!(pow_mod(MAX / 3, M127 - 1, M127), 1);
+
+		let cases = [
+			(3, 7, 1_000_000_007),
+			(MAX - 1, 5, MAX),
+			(MAX / 7, 9, MAX - 58),
+			(1 << 100, 3, (1 << 127) + 1),
+			(M127 - 2, 11, M127),
+		];
+		for (base, exp, modulus) in cases {
+			assert_eq!(pow_mod(base, exp as u128, modulus), pow_mod_reference(base, exp, modulus));
+		}
+	}
+
+	#[test]
+	#[should_panic(expected = "divisor of zero")]
+	fn pow_mod_zero_modulus_panics() {
+		pow_mod(2, 3, 0);
+	}
+
+	#[test]
+	fn mul_mod_works() {
+		assert_eq!(mul_mod(7, 8, 5), 1);
+		assert_eq!(mul_mod(0, MAX, 3), 0);
+		assert_eq!(mul_mod(MAX, MAX, 1), 0);
+		// `MAX ≡ 1 (mod MAX - 1)`
+		assert_eq!(mul_mod(MAX, MAX, MAX - 1), 1);
+		assert_eq!(mul_mod(MAX, MAX, MAX), 0);
+		// `2^64 * 2^64 = 2^128 ≡ 1 (mod MAX)`
+		assert_eq!(mul_mod(1 << 64, 1 << 64, MAX), 1);
+		// `2^127 ≡ 1 (mod 2^127 - 1)`
+		assert_eq!(mul_mod(1 << 127, 1 << 127, (1 << 127) - 1), 1);
+		assert!(MAX.checked_mul(MAX / 3).is_none());
+		assert_eq!(mul_mod(MAX, MAX / 3, 1_000_000_007), 744526909);
+	}
+
+	#[test]
+	#[should_panic(expected = "divisor of zero")]
+	fn mul_mod_zero_modulus_panics() {
+		mul_mod(2, 3, 0);
+	}
+
+	#[test]
+	fn reduce_double128_over_u128_works() {
+		// 2^129 / 24 doesn't have a `u128` numerator, 2^126 / 3 does.
+		let num = Double128::product_of(1 << 127, 4);
+		assert!(num.try_into_u128().is_none());
+		let (reduced, den) = reduce_double128_over_u128(num, 24);
+		assert_eq!(reduced.try_into_u128(), Some(1 << 126));
+		assert_eq!(den, 3);
+
+		// co-prime fractions are left as-is.
+		let num = Double128::product_of(MAX, 3);
+		assert_eq!(reduce_double128_over_u128(num, 7), (num, 7));
+
+		// exact divisions reduce to a unit denominator.
+		let num = Double128::product_of(MAX, MAX);
+		let (reduced, den) = reduce_double128_over_u128(num, MAX);
+		assert_eq!((reduced.try_into_u128(), den), (Some(MAX), 1));
+
+		assert_eq!(reduce_double128_over_u128(num, 0), (num, 0));
+	}
+
+	#[test]
+	#[cfg(feature = "rational-path-stats")]
+	fn path_stats_count_both_paths() {
+		// other tests may run concurrently, so only check that the counters moved.
+		let (fast, big) = path_stats();
+		assert_eq!(multiply_by_rational(10, 20, 3), Ok(66));
+		assert!(multiply_by_rational(MAX - 1, MAX / 2, MAX).is_ok());
+		let (new_fast, new_big) = path_stats();
+		assert!(new_fast > fast);
+		assert!(new_big > big);
+	}
+
+	#[test]
+	fn saturating_multiply_by_rational_with_rounding_works() {
+		assert_eq!(saturating_multiply_by_rational_with_rounding(10, 20, 3, Up), 67);
+		assert_eq!(saturating_multiply_by_rational_with_rounding(10, 20, 3, Down), 66);
+		// the quotient alone overflows.
+		assert_eq!(multiply_by_rational_with_rounding(MAX, 3, 2, Down), None);
+		assert_eq!(saturating_multiply_by_rational_with_rounding(MAX, 3, 2, Down), MAX);
+		// `(MAX - 1)^2 / (MAX - 2)` is `MAX` with a remainder of 1, rounding up overflows.
+		let (a, c) = (MAX - 1, MAX - 2);
+		assert_eq!(multiply_by_rational_with_rounding(a, a, c, Down), Some(MAX));
+		assert_eq!(multiply_by_rational_with_rounding(a, a, c, Up), None);
+		assert_eq!(saturating_multiply_by_rational_with_rounding(a, a, c, Up), MAX);
+	}
+
+	#[test]
+	#[should_panic(expected = "attempt to divide by zero")]
+	fn saturating_multiply_by_rational_with_rounding_zero_panics() {
+		saturating_multiply_by_rational_with_rounding(1, 1, 0, Up);
+	}
+
+	#[test]
+	fn compound_works() {
+		assert_eq!(compound(1_000, 11, 10, 0, Down), Some(1_000));
+		assert_eq!(compound(1_000, 11, 10, 1, Down), Some(1_100));
+		// 1000 * 1.1^3 = 1331
+		assert_eq!(compound(1_000, 11, 10, 3, Down), Some(1_331));
+		// 1000 * 1.05^2 = 1102.5, rounded per period: 1050 -> 1102.5
+		assert_eq!(compound(1_000, 105, 100, 2, Down), Some(1_102));
+		assert_eq!(compound(1_000, 105, 100, 2, Up), Some(1_103));
+		// decaying rates shrink towards zero.
+		assert_eq!(compound(1_000, 1, 2, 3, Down), Some(125));
+		assert_eq!(compound(MAX / 2, 3, 1, 1, Down), None);
+		assert_eq!(compound(2, 2, 1, 127, Down), None);
+		assert_eq!(compound(1, 2, 1, 127, Down), Some(1 << 127));
+		assert_eq!(compound(1_000, 11, 0, 1, Down), None);
+	}
+
+	#[test]
+	fn compound_rate_works() {
+		let percent = |x: u64| Perquintill::from_percent(x);
+		assert_eq!(compound_rate(percent(10), 0), Perquintill::zero());
+		assert_eq!(compound_rate(Perquintill::zero(), 1_000_000), Perquintill::zero());
+		assert_eq!(compound_rate(percent(10), 1), percent(10));
+		assert_eq!(compound_rate(percent(10), 2), percent(21));
+		assert_eq!(compound_rate(percent(10), 3), Perquintill::from_parts(331_000_000_000_000_000));
+
+		// saturates at one.
+		assert_eq!(compound_rate(percent(100), 1), Perquintill::one());
+		assert_eq!(compound_rate(percent(50), 2), Perquintill::one());
+		assert_eq!(compound_rate(percent(1), 69), Perquintill::from_parts(986_894_424_153_845_260));
+		assert_eq!(compound_rate(percent(1), 70), Perquintill::one());
+
+		// 4294967295 * 10^-18 + 4294967295 * 4294967294 / 2 * 10^-36 + ...
+		let smallest = compound_rate(Perquintill::from_parts(1), u32::MAX);
+		assert_eq!(smallest, Perquintill::from_parts(4_294_967_304));
+
+		// a year of six second blocks: (1 + 10^-9)^5_256_000 - 1 = 0.0052698369971599030...
+		let yearly = compound_rate(Perquintill::from_parts(1_000_000_000), 5_256_000);
+		assert_eq!(yearly, Perquintill::from_parts(5_269_836_997_159_903));
+	}
+
+	#[test]
+	fn compound_rate_is_within_one_part_for_long_horizons() {
+		// `(1 + rate)^periods - 1` in parts, rounded to the nearest, where the fractional parts of
+		// the exact values are .034, .704, .207 and .838.
+		let cases = [
+			(127_672_328, u32::MAX, 730_392_866_518_219_322),
+			(100_000_000, u32::MAX - 1, 536_484_061_645_689_880),
+			(50_000_000, 3_000_000_000, 161_834_242_723_926_244),
+			(12_345, u32::MAX, 53_022_776_914_523),
+		];
+		for (rate, periods, expected) in cases {
+			let actual = compound_rate(Perquintill::from_parts(rate), periods);
+			assert_eq!(actual, Perquintill::from_parts(expected), "{} over {}", rate, periods);
+		}
+	}
+
+	#[test]
+	fn compound_rate_is_within_one_part() {
+		let accuracy = to_big_uint(Perquintill::ACCURACY as u128);
+		use rand::{rngs::StdRng, Rng, SeedableRng};
+		let mut rng = StdRng::seed_from_u64(0x6a09_e667_f3bc_c908);
+		for _ in 0..200 {
+			let rate = Perquintill::from_parts(rng.gen_range(0, 10_000_000_000_000_000));
+			let periods: u32 = rng.gen_range(1, 64);
+			// floor(ACCURACY * (1 + rate)^periods), computed exactly.
+			let base = to_big_uint(Perquintill::ACCURACY as u128 + rate.deconstruct() as u128);
+			let (mut numerator, mut denominator) = (accuracy.clone(), biguint::BigUint::one());
+			for _ in 0..periods {
+				numerator = numerator.mul(&base);
+				denominator = denominator.mul(&accuracy);
+			}
+			let factor = biguint_div_rounded(&numerator, &denominator, Rounding::Down);
+			// `(1.01)^63` is less than two, so this never saturates.
+			let exact = u128::try_from(factor).unwrap() - Perquintill::ACCURACY as u128;
+			let actual = compound_rate(rate, periods).deconstruct() as u128;
+			assert!(actual == exact || actual == exact + 1, "{:?} over {}", rate, periods);
+		}
+	}
+
+	#[test]
+	fn factorial_and_binomial_work() {
+		let big = |x: u128| to_big_uint(x);
+		assert_eq!(factorial_big(0), big(1));
+		assert_eq!(factorial_big(1), big(1));
+		assert_eq!(factorial_big(5), big(120));
+		assert_eq!(factorial_big(30), big(265252859812191058636308480000000));
+		// 40! doesn't fit in a `u128`, check it as `20! * (40! / 20!)`.
+		assert_eq!(
+			factorial_big(40),
+			big(2432902008176640000).mul(&big(335367096786357081410764800000))
+		);
+
+		assert_eq!(binomial_big(5, 2), big(10));
+		assert_eq!(binomial_big(5, 0), big(1));
+		assert_eq!(binomial_big(5, 5), big(1));
+		assert_eq!(binomial_big(5, 6), big(0));
+		assert_eq!(binomial_big(100, 50), big(100891344545564193334812497256));
+		assert_eq!(binomial_big(u32::max_value(), 1), big(u32::max_value() as u128));
+		assert_eq!(binomial_big(40, 20), big(137846528820));
+	}
+
+	#[test]
+	fn le_limbs_work() {
+		const X: u128 = 0x0011_2233_4455_6677_8899_aabb_ccdd_eeff;
+		assert_eq!(to_le_limbs(X), [0x8899_aabb_ccdd_eeff, 0x0011_2233_4455_6677]);
+		assert_eq!(from_le_limbs([0x8899_aabb_ccdd_eeff, 0x0011_2233_4455_6677]), X);
+		assert_eq!(to_le_limbs(X), [split(X).1, split(X).0]);
+		for x in [0, 1, X, MAX, 1 << 64, (1 << 64) - 1] {
+			assert_eq!(from_le_limbs(to_le_limbs(x)), x);
+		}
+
+		// byte order matches the limb order.
+		let bytes = X.to_le_bytes();
+		assert_eq!(bytes[..8], 0x8899_aabb_ccdd_eeff_u64.to_le_bytes());
+		assert_eq!(X.to_be_bytes()[0], 0x00);
+		assert_eq!(X.to_be_bytes()[15], 0xff);
+		assert_eq!(u128::from_be_bytes(X.to_be_bytes()), X);
+	}
+
+	#[test]
+	fn cmp_rationals_works() {
+		assert_eq!(cmp_rationals(1, 2, 2, 4), Ordering::Equal);
+		assert_eq!(cmp_rationals(1, 3, 1, 2), Ordering::Less);
+		assert_eq!(cmp_rationals(MAX, MAX - 1, MAX - 1, MAX - 2), Ordering::Less);
+		assert_eq!(cmp_rationals(MAX, 1, MAX - 1, 1), Ordering::Greater);
+		assert_eq!(cmp_rationals(MAX, MAX, 1, 1), Ordering::Equal);
+	}
+
+	#[test]
+	fn clamp_ratio_works() {
+		let (lo, hi) = ((1, 4), (3, 4));
+		assert_eq!(clamp_ratio(1, 10, lo, hi), Ok(lo));
+		assert_eq!(clamp_ratio(1, 2, lo, hi), Ok((1, 2)));
+		assert_eq!(clamp_ratio(2, 8, lo, hi), Ok((2, 8)));
+		assert_eq!(clamp_ratio(9, 10, lo, hi), Ok(hi));
+		assert_eq!(clamp_ratio(MAX, MAX - 1, (1, 1), (MAX - 1, MAX - 2)), Ok((MAX, MAX - 1)));
+		assert_eq!(clamp_ratio(MAX, 1, lo, (MAX - 1, 1)), Ok((MAX - 1, 1)));
+
+		assert!(clamp_ratio(1, 0, lo, hi).is_err());
+		assert!(clamp_ratio(1, 2, (1, 0), hi).is_err());
+		assert!(clamp_ratio(1, 2, lo, (1, 0)).is_err());
+	}
+
+	#[test]
+	fn multiply_by_rational_signed_works() {
+		use RationalError::*;
+		let mul = multiply_by_rational_signed;
+
+		assert_eq!(mul(7, 1, 2, Down), Ok(3));
+		assert_eq!(mul(7, 1, 2, Up), Ok(4));
+		assert_eq!(mul(7, 1, 2, Nearest), Ok(3));
+		assert_eq!(mul(-7, 1, 2, Down), Ok(-4));
+		assert_eq!(mul(7, -1, 2, Up), Ok(-3));
+		assert_eq!(mul(7, 1, -2, Nearest), Ok(-4));
+		assert_eq!(mul(-7, -1, 2, Down), Ok(3));
+		assert_eq!(mul(-8, 1, 3, Nearest), Ok(-3));
+		assert_eq!(mul(-7, 1, 3, Nearest), Ok(-2));
+		assert_eq!(mul(-6, 1, 3, Down), Ok(-2));
+		assert_eq!(mul(0, -5, 3, Up), Ok(0));
+
+		assert_eq!(mul(i128::MIN, 1, 1, Down), Ok(i128::MIN));
+		assert_eq!(mul(i128::MAX, i128::MAX, i128::MAX, Down), Ok(i128::MAX));
+		assert_eq!(mul(i128::MIN, i128::MAX, -i128::MAX, Up), Err(Overflow));
+		assert_eq!(mul(i128::MIN, 1, -1, Down), Err(Overflow));
+		assert_eq!(mul(i128::MAX, 2, 1, Down), Err(Overflow));
+		assert_eq!(mul(1, 1, 0, Down), Err(DivByZero));
+	}
+
+	#[test]
+	fn quad128_works() {
+		use double128::Quad128;
+		let reference = |a: u128, b: u128, c: u128, d: u128| {
+			let abc = to_big_uint(a).mul(&to_big_uint(b)).mul(&to_big_uint(c));
+			let d = to_big_uint(d);
+			let mut q = biguint_div_rounded(&abc, &d, Down);
+			let mut r = abc.sub(&q.clone().mul(&d)).unwrap();
+			q.lstrip();
+			r.lstrip();
+			(q.try_into().ok(), r.try_into().unwrap())
+		};
+		let quad = |a, b, c, d| {
+			let (q, r) = Quad128::product_of_double(Double128::product_of(a, b), c).div(d);
+			(q.try_into_u128(), r)
+		};
+
+		for (a, b, c, d) in [
+			(MAX, MAX, MAX, MAX),
+			(MAX - 1, MAX - 2, MAX - 3, MAX),
+			(MAX, MAX - 7, MAX / 3, MAX - 1),
+			(MAX, MAX, MAX, 1 << 127),
+			(MAX, MAX, MAX, 1_000_000_007),
+			(u64::MAX as u128, MAX, 3, MAX / 2),
+			(123, 456, 789, 10),
+		] {
+			assert_eq!(quad(a, b, c, d), reference(a, b, c, d), "{} * {} * {} / {}", a, b, c, d);
+		}
+		assert_eq!(quad(MAX, MAX, MAX, MAX), (None, 0));
+		assert_eq!(quad(MAX, MAX - 1, MAX - 2, MAX).0, None);
+		assert_eq!(quad(6, 7, 8, 5), (Some(67), 1));
+	}
+
+	#[test]
+	fn multiply_by_rational_u64_works() {
+		assert_eq!(multiply_by_rational_u64(7, 1, 2, Down), 3);
+		assert_eq!(multiply_by_rational_u64(7, 1, 2, Nearest), 3);
+		assert_eq!(multiply_by_rational_u64(7, 1, 2, Up), 4);
+		assert_eq!(multiply_by_rational_u64(u64::MAX, u64::MAX, u64::MAX, Up), u64::MAX);
+		assert_eq!(multiply_by_rational_u64(u64::MAX, 2, 1, Down), u64::MAX);
+		assert_eq!(multiply_by_rational_u64(u64::MAX, u64::MAX, u64::MAX - 1, Down), u64::MAX);
+
+		// Compare against the `u128` version over pseudo-random triples.
+		use rand::{rngs::StdRng, Rng, SeedableRng};
+		let mut rng = StdRng::seed_from_u64(0x853c_49e6_748f_ea9b);
+		let mut next = || {
+			let x: u64 = rng.gen();
+			x >> (x % 48)
+		};
+		for _ in 0..10_000 {
+			let (a, b, c) = (next(), next(), next().max(1));
+			for r in [Up, Down, Nearest, ToEven] {
+				let expected = multiply_by_rational_with_rounding(a.into(), b.into(), c.into(), r)
+					.unwrap()
+					.min(u64::MAX as u128);
+				assert_eq!(multiply_by_rational_u64(a, b, c, r) as u128, expected);
+			}
+		}
+	}
+
+	#[test]
+	fn narrow_path_matches_wide_path() {
+		use rand::{rngs::StdRng, Rng, SeedableRng};
+		let mut rng = StdRng::seed_from_u64(0x2545_f491_4f6c_dd1d);
+		let mut next = || {
+			let x: u64 = rng.gen();
+			x >> (x % 64)
+		};
+		for _ in 0..10_000 {
+			let (a, b) = (next() as u128, next() as u128);
+			let c = ((next() as u128) << (next() % 64)).max(1);
+			for r in [Up, Down, Nearest, ToEven] {
+				assert_eq!(
+					multiply_by_rational_with_rounding(a, b, c, r),
+					multiply_by_rational_wide(a, b, c, r),
+					"{} * {} / {} rounding {:?}",
+					a,
+					b,
+					c,
+					r,
+				);
+			}
+		}
+
+		// the largest products of the narrow path.
+		let max = u64::MAX as u128;
+		for r in [Up, Down, Nearest, ToEven] {
+			assert_eq!(multiply_by_rational_with_rounding(max, max, 1, r), Some(max * max));
+			assert_eq!(
+				multiply_by_rational_with_rounding(max, max, MAX, r),
+				multiply_by_rational_wide(max, max, MAX, r)
+			);
+		}
+	}
+
+	#[test]
+	#[cfg(target_pointer_width = "64")]
+	fn try_usize_works() {
+		assert_eq!(try_usize(0), Some(0));
+		assert_eq!(try_usize(42), Some(42));
+		assert_eq!(try_usize(u32::MAX as u128 + 1), Some(1 << 32));
+		assert_eq!(try_usize(u64::MAX as u128), Some(usize::MAX));
+		assert_eq!(try_usize(u64::MAX as u128 + 1), None);
+		assert_eq!(try_usize(MAX), None);
+	}
+
+	#[test]
+	fn reduce_all_works() {
+		assert_eq!(reduce_all(&[6, 9, 12]), (3, vec![2, 3, 4]));
+		assert_eq!(reduce_all(&[0, 8, 12]), (4, vec![0, 2, 3]));
+		assert_eq!(reduce_all(&[MAX, MAX]), (MAX, vec![1, 1]));
+		assert_eq!(reduce_all(&[5, 7]), (1, vec![5, 7]));
+		assert_eq!(reduce_all(&[0, 0]), (0, vec![0, 0]));
+		assert_eq!(reduce_all(&[]), (0, vec![]));
+	}
+
+	#[test]
+	fn double128_div_double_works() {
+		let to_big = |x: Double128| {
+			let (high, low) = x.into_parts();
+			(biguint::BigUint::from(high) << 128) + biguint::BigUint::from(low)
+		};
+		let check = |a: Double128, b: Double128| {
+			let (q, r) = a.div_double(b);
+			assert_eq!(to_big(q) * to_big(b) + to_big(r), to_big(a), "{:?} / {:?}", a, b);
+			assert!(to_big(r) < to_big(b), "{:?} % {:?}", a, b);
+			(q, r)
+		};
+		let double = |high: u128, low: u128| {
+			let half = Double128::product_of(high, 1 << 127);
+			half.add(half).add(Double128::from(low))
+		};
+		let max = double(MAX, MAX);
+
+		assert_eq!(check(max, max), (Double128::from(1), Double128::zero()));
+		assert_eq!(check(max, double(1, 0)), (Double128::from(MAX), Double128::from(MAX)));
+		assert_eq!(check(double(1, 0), max), (Double128::zero(), double(1, 0)));
+		assert_eq!(check(max, Double128::from(MAX)), (double(1, 1), Double128::zero()));
+		assert_eq!(check(max, double(MAX >> 1, MAX)), (Double128::from(2), Double128::from(1)));
+		assert_eq!(
+			check(Double128::product_of(MAX, MAX), Double128::product_of(MAX, 3)),
+			(Double128::from(MAX / 3), Double128::zero())
+		);
+		// the first estimate of the quotient is one too large even after refining it.
+		assert_eq!(
+			check(double(0x7fff_ffff_ffff_ffff_8000_0000_0000_0000, 0), double(1 << 63, 1)),
+			(
+				Double128::from(u64::MAX as u128 - 1),
+				double(u64::MAX as u128 >> 1, 0xffff_ffff_ffff_ffff_0000_0000_0000_0002)
+			)
+		);
+
+		use rand::{rngs::StdRng, Rng, SeedableRng};
+		let mut rng = StdRng::seed_from_u64(42);
+		let mut next = || {
+			let high: u128 = rng.gen();
+			double(high >> rng.gen_range(0u32, 128), rng.gen())
+		};
+		for _ in 0..10_000 {
+			let (a, b) = (next(), next());
+			check(a, b);
+		}
+	}
+
+	#[test]
+	fn double128_sum_of_products_works() {
+		let pairs = [(MAX / 2, MAX), (MAX - 1, 3), (1 << 100, 1 << 27), (0, MAX)];
+		let expected = pairs.iter().fold(biguint::BigUint::zero(), |acc, (a, b)| {
+			acc.add(&to_big_uint(*a).mul(&to_big_uint(*b)))
+		});
+		let (high, low) = Double128::sum_of_products(&pairs).unwrap().into_parts();
+		assert_eq!((biguint::BigUint::from(high) << 128) + biguint::BigUint::from(low), expected);
+		assert_eq!(Double128::sum_of_products(&[]), Some(Double128::zero()));
+
+		// a final division brings the weighted sum back to a `u128`.
+		let stakes = [(MAX / 2, 3), (MAX / 2, 1)];
+		let (average, _) = Double128::sum_of_products(&stakes).unwrap().div(4);
+		assert_eq!(average.try_into_u128(), Some(MAX / 2));
+		assert_eq!(Double128::sum_of_products(&stakes).unwrap().try_into_u128(), None);
+
+		// the largest value fits, one more does not.
+		let max = Double128::product_of(MAX, MAX)
+			.add(Double128::from(MAX))
+			.add(Double128::from(MAX));
+		assert_eq!(max.checked_add(Double128::zero()), Some(max));
+		assert_eq!(max.checked_add(Double128::from(1)), None);
+		assert_eq!(
+			Double128::product_of(MAX, MAX)
+				.checked_add(Double128::from(MAX))
+				.unwrap()
+				.into_parts(),
+			(MAX, 0)
+		);
+		assert_eq!(Double128::sum_of_products(&[(MAX, MAX), (MAX, 2)]), Some(max));
+		assert_eq!(Double128::sum_of_products(&[(MAX, MAX), (MAX, 3)]), None);
+
+		// values are ordered numerically.
+		assert!(Double128::from(MAX) < Double128::product_of(MAX, 2));
+		assert!(Double128::product_of(1 << 64, 1 << 64) > Double128::from(MAX));
+		assert!(Double128::product_of(MAX, 3) > Double128::product_of(MAX - 1, 3));
+	}
+
+	#[test]
+	fn double128_checked_mul_u128_works() {
+		let max = Double128::product_of(MAX, MAX);
+		assert_eq!(Double128::from_low(6).checked_mul_u128(7), Some(Double128::from_low(42)));
+		assert_eq!(Double128::from_low(MAX).checked_mul_u128(MAX), Some(max));
+		assert_eq!(max.checked_mul_u128(1), Some(max));
+		assert_eq!(max.checked_mul_u128(0), Some(Double128::zero()));
+		assert_eq!(max.checked_mul_u128(2), None);
+
+		// 2^255 is the largest power of two that fits, 2^256 does not.
+		let quarter = Double128::product_of(1 << 127, 1 << 127);
+		assert_eq!(quarter.checked_mul_u128(2), Some(quarter.add(quarter)));
+		assert_eq!(quarter.checked_mul_u128(4), None);
+		assert_eq!(
+			Double128::from_low(1 << 127).checked_mul_u128(MAX),
+			Some(Double128::product_of(1 << 127, MAX))
+		);
+	}
+
+	#[test]
+	fn rational_to_u128_works() {
+		assert_eq!(rational_to_u128(7, 2, Down), Some(3));
+		assert_eq!(rational_to_u128(7, 2, Up), Some(4));
+		assert_eq!(rational_to_u128(7, 2, Nearest), Some(3));
+		assert_eq!(rational_to_u128(8, 3, Nearest), Some(3));
+		assert_eq!(rational_to_u128(6, 3, Up), Some(2));
+		assert_eq!(rational_to_u128(MAX, 1, Up), Some(MAX));
+		assert_eq!(rational_to_u128(MAX, 2, Up), Some(MAX / 2 + 1));
+		assert_eq!(rational_to_u128(7, 0, Down), None);
+	}
+
+	#[test]
+	fn per_thing_from_rational_saturating_works() {
+		use crate::Permill;
+		let from = per_thing_from_rational_saturating::<Permill>;
+		assert_eq!(from(1, 4), Permill::from_parts(250_000));
+		assert_eq!(from(4, 4), Permill::one());
+		assert_eq!(from(5, 4), Permill::one());
+		assert_eq!(from(1, 3), Permill::from_parts(333_333));
+		assert_eq!(from(2, 3), Permill::from_parts(666_667));
+		assert_eq!(from(MAX - 1, MAX), Permill::one());
+		assert_eq!(from(1, MAX), Permill::zero());
+		assert_eq!(from(1, 0), Permill::zero());
+	}
+
+	#[test]
+	fn rational_multiply_is_commutative() {
+		use rand::{rngs::StdRng, Rng, SeedableRng};
+		let mut rng = StdRng::seed_from_u64(0x2545_f491_4f6c_dd1d);
+		let mut big_path = 0;
+		for _ in 0..10_000 {
+			let mut value = || {
+				let v: u128 = rng.gen();
+				v >> rng.gen_range(0u32, 128)
+			};
+			let (a, b, c) = (value(), value(), value().max(1));
+			if a.checked_mul(b).is_none() {
+				big_path += 1;
+			}
+			assert_eq!(multiply_by_rational(a, b, c), multiply_by_rational(b, a, c));
+			for r in [Up, Down, Nearest, ToEven] {
+				assert_eq!(
+					multiply_by_rational_with_rounding(a, b, c, r),
+					multiply_by_rational_with_rounding(b, a, c, r),
+				);
+			}
+		}
+		assert!(big_path > 1_000);
+	}
+
+	#[test]
+	fn biguint_sqrt_works() {
+		let sqrt = |n: u128| -> u128 { biguint_sqrt(&to_big_uint(n)).try_into().unwrap() };
+		for n in [0, 1, 2, 3, 4, 15, 16, 17, 99, 100, 1 << 64, (1 << 64) - 1, MAX] {
+			let root = sqrt(n);
+			assert!(root * root <= n, "{}", n);
+			assert!((root + 1).checked_mul(root + 1).map_or(true, |sq| sq > n), "{}", n);
+		}
+		assert_eq!(sqrt(MAX), u64::MAX as u128);
+
+		let mut square = to_big_uint(MAX).mul(&to_big_uint(MAX));
+		square.lstrip();
+		assert_eq!(biguint_sqrt(&square), to_big_uint(MAX));
+	}
+
+	#[test]
+	fn hypot_works() {
+		assert_eq!(sum_of_squares(3, 4), to_big_uint(25));
+		assert_eq!(hypot_floor(3, 4), Some(5));
+		assert_eq!(hypot_floor(0, 0), Some(0));
+		assert_eq!(hypot_floor(1, 1), Some(1));
+
+		// (3k)^2 + (4k)^2 == (5k)^2
+		let k = MAX / 5;
+		assert_eq!(hypot_floor(3 * k, 4 * k), Some(5 * k));
+		assert_eq!(hypot_floor(MAX, 0), Some(MAX));
+		assert_eq!(hypot_floor(MAX, 1), Some(MAX));
+		assert_eq!(hypot_floor(MAX, MAX), None);
+
+		let (a, b) = (MAX / 2, MAX / 3);
+		let root = hypot_floor(a, b).unwrap();
+		let square = |x: u128| to_big_uint(x).mul(&to_big_uint(x));
+		assert!(square(root) <= sum_of_squares(a, b));
+		assert!(square(root + 1) > sum_of_squares(a, b));
+	}
+
+	#[test]
+	fn clamp_u128_works() {
+		const CLAMPED: u128 = clamp_u128(1, 5, 10);
+		assert_eq!(CLAMPED, 5);
+		assert_eq!(clamp_u128(7, 5, 10), 7);
+		assert_eq!(clamp_u128(5, 5, 10), 5);
+		assert_eq!(clamp_u128(10, 5, 10), 10);
+		assert_eq!(clamp_u128(MAX, 5, 10), 10);
+		assert_eq!(clamp_u128(3, 3, 3), 3);
+	}
+
+	#[test]
+	#[should_panic(expected = "lo <= hi")]
+	fn clamp_u128_panics_on_empty_range() {
+		clamp_u128(7, 10, 5);
+	}
+
+	#[test]
+	fn weighted_median_works() {
+		assert_eq!(weighted_median(&[]), None);
+		assert_eq!(weighted_median(&[(5, 0), (7, 0)]), None);
+		assert_eq!(weighted_median(&[(42, 1)]), Some(42));
+		assert_eq!(weighted_median(&[(30, 1), (10, 1), (20, 1)]), Some(20));
+		assert_eq!(weighted_median(&[(10, 1), (20, 1), (30, 5)]), Some(30));
+		assert_eq!(weighted_median(&[(10, 0), (20, 0), (30, 5)]), Some(30));
+
+		// An even split returns the lower value.
+		assert_eq!(weighted_median(&[(20, 3), (10, 3)]), Some(10));
+		assert_eq!(weighted_median(&[(10, 2), (20, 1), (30, 3)]), Some(20));
+		assert_eq!(weighted_median(&[(10, 1), (20, 0), (30, 1)]), Some(10));
+
+		// Weights whose total overflows a `u128`.
+		assert_eq!(weighted_median(&[(1, MAX), (2, MAX), (3, MAX)]), Some(2));
+		assert_eq!(weighted_median(&[(1, MAX), (2, MAX)]), Some(1));
+	}
+
+	#[test]
+	fn mul_wide_works() {
+		let reference = |a: u128, b: u128| {
+			let mut product = to_big_uint(a).mul(&to_big_uint(b));
+			product.lpad(8);
+			let (high, low) = product.digits.split_at(4);
+			let mut high = biguint::BigUint::from_limbs(high);
+			let mut low = biguint::BigUint::from_limbs(low);
+			high.lstrip();
+			low.lstrip();
+			(high.try_into().unwrap(), low.try_into().unwrap())
+		};
+		for (a, b) in [
+			(0, 0),
+			(1, MAX),
+			(MAX, MAX),
+			(MAX, 2),
+			(1 << 64, 1 << 64),
+			(u64::MAX as u128, u64::MAX as u128 + 2),
+			(MAX / 3, MAX / 7),
+		] {
+			assert_eq!(mul_wide(a, b), reference(a, b), "{} * {}", a, b);
+		}
+		assert_eq!(mul_wide(MAX, MAX), (MAX - 1, 1));
+		assert_eq!(mul_wide(1 << 64, 1 << 64), (1, 0));
+	}
+
+	#[test]
+	fn mod_inverse_works() {
+		assert_eq!(mod_inverse(3, 11), Some(4));
+		assert_eq!(mod_inverse(10, 11), Some(10));
+		assert_eq!(mod_inverse(1, 1), Some(0));
+		assert_eq!(mod_inverse(6, 9), None);
+		assert_eq!(mod_inverse(0, 11), None);
+		assert_eq!(mod_inverse(22, 11), None);
+		assert_eq!(mod_inverse(3, 0), None);
+
+		// 2^127 - 1 is prime.
+		let p = (1 << 127) - 1;
+		for (a, m) in
+			[(3, 11), (7, 1_000_000_007), (MAX, MAX - 1), (12345, p), (p - 1, p), (2, MAX)]
+		{
+			let inv = mod_inverse(a, m).unwrap();
+			assert!(inv < m);
+			assert_eq!(mul_mod(a, inv, m), 1, "{}^-1 mod {}", a, m);
+		}
+	}
+
+	#[test]
+	fn rescale_works() {
+		assert_eq!(rescale(1, 3, 1_000_000, Down), Some(333_333));
+		assert_eq!(rescale(1, 3, 1_000_000, Up), Some(333_334));
+		assert_eq!(rescale(2, 3, 1_000_000, Nearest), Some(666_667));
+		assert_eq!(rescale(MAX, MAX, 1_000_000, Down), Some(1_000_000));
+		assert_eq!(rescale(MAX / 2, MAX, 10, Nearest), Some(5));
+		assert_eq!(rescale(MAX, 1, 2, Down), None);
+		assert_eq!(rescale(1, 0, 1_000_000, Down), None);
+	}
+
+	#[test]
+	fn lerp_works() {
+		assert_eq!(lerp(10, 20, 1, 4, Down), Some(12));
+		assert_eq!(lerp(10, 20, 1, 4, Up), Some(13));
+		assert_eq!(lerp(10, 20, 1, 4, Nearest), Some(12));
+		assert_eq!(lerp(10, 20, 3, 4, Nearest), Some(17));
+		assert_eq!(lerp(10, 20, 2, 3, Nearest), Some(17));
+
+		assert_eq!(lerp(20, 10, 1, 4, Down), Some(17));
+		assert_eq!(lerp(20, 10, 1, 4, Up), Some(18));
+		assert_eq!(lerp(20, 10, 1, 4, Nearest), Some(17));
+		assert_eq!(lerp(20, 10, 1, 3, Nearest), Some(17));
+		assert_eq!(lerp(20, 10, 2, 3, Nearest), Some(13));
+
+		assert_eq!(lerp(0, MAX, 1, 2, Down), Some(MAX / 2));
+		assert_eq!(lerp(MAX, 0, 1, 2, Down), Some(MAX / 2));
+		assert_eq!(lerp(MAX, 0, 1, 2, Up), Some(MAX / 2 + 1));
+		assert_eq!(lerp(5, 5, 1, 3, Up), Some(5));
+		assert_eq!(lerp(10, 20, 0, 4, Up), Some(10));
+
+		assert_eq!(lerp(10, 20, 5, 4, Down), Some(20));
+		assert_eq!(lerp(20, 10, 4, 4, Up), Some(10));
+		assert_eq!(lerp(10, 20, 1, 0, Down), None);
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn ensure_fits_u128_works() {
+		assert_eq!(ensure_fits_u128(&to_big_uint(MAX), "max"), Ok(MAX));
+		assert_eq!(ensure_fits_u128(&to_big_uint(0), "zero"), Ok(0));
+
+		let mut padded = to_big_uint(42);
+		padded.lpad(8);
+		assert_eq!(ensure_fits_u128(&padded, "padded"), Ok(42));
+
+		let doubled = to_big_uint(MAX).mul(&to_big_uint(2));
+		assert_eq!(
+			ensure_fits_u128(&doubled, "doubling max"),
+			Err("doubling max: value exceeds u128".to_owned())
+		);
+	}
+
+	#[test]
+	#[cfg(feature = "std")]
+	fn double128_to_decimal_string_works() {
+		assert_eq!(Double128::zero().to_decimal_string(), "0");
+		assert_eq!(Double128::from_low(7).to_decimal_string(), "7");
+		assert_eq!(Double128::from_low(MAX).to_decimal_string(), MAX.to_string());
+		assert_eq!(
+			Double128::product_of(MAX, 2).to_decimal_string(),
+			"680564733841876926926749214863536422910"
+		);
+		// (2^128 - 1)^2 and 2^128 + 1.
+		assert_eq!(
+			Double128::product_of(MAX, MAX).to_decimal_string(),
+			"115792089237316195423570985008687907852589419931798687112530834793049593217025"
+		);
+		assert_eq!(
+			Double128::from_low(MAX).add(Double128::from_low(2)).to_decimal_string(),
+			"340282366920938463463374607431768211457"
+		);
+	}
+
+	#[test]
+	fn multiply_by_rational_signed_with_works() {
+		use RationalError::*;
+		use SignedRounding::*;
+		let mul = multiply_by_rational_signed_with;
+
+		for (sign, a) in [(1, 7), (-1, -7)] {
+			assert_eq!(mul(a, 1, 2, TowardZero), Ok(sign * 3));
+			assert_eq!(mul(a, 1, 2, AwayFromZero), Ok(sign * 4));
+			assert_eq!(mul(a, 1, 2, Nearest), Ok(sign * 3));
+			assert_eq!(mul(a, 2, 3, Nearest), Ok(sign * 5));
+			assert_eq!(mul(a, 1, 3, Nearest), Ok(sign * 2));
+			assert_eq!(mul(a, -1, -2, AwayFromZero), Ok(sign * 4));
+		}
+		assert_eq!(mul(7, -1, 2, TowardZero), Ok(-3));
+		assert_eq!(mul(7, 1, -2, AwayFromZero), Ok(-4));
+		assert_eq!(mul(0, -5, 3, AwayFromZero), Ok(0));
+
+		assert_eq!(mul(i128::MIN, i128::MAX, i128::MAX, TowardZero), Ok(i128::MIN));
+		assert_eq!(mul(i128::MIN, 1, -1, TowardZero), Err(Overflow));
+		assert_eq!(mul(i128::MAX, 3, 2, Nearest), Err(Overflow));
+		assert_eq!(mul(1, 1, 0, Nearest), Err(DivByZero));
+	}
+
+	#[test]
+	fn sqrt_of_product_works() {
+		assert_eq!(sqrt_of_product(0, MAX, Up), 0);
+		assert_eq!(sqrt_of_product(4, 9, Up), 6);
+		assert_eq!(sqrt_of_product(4, 9, Down), 6);
+		assert_eq!(sqrt_of_product(MAX, MAX, Up), MAX);
+		assert_eq!(sqrt_of_product(MAX, MAX - 1, Up), MAX);
+		assert_eq!(sqrt_of_product(MAX, MAX - 1, Down), MAX - 1);
+
+		// sqrt(2) = 1.414..., sqrt(6) = 2.449... and sqrt(12) = 3.464...
+		assert_eq!(sqrt_of_product(1, 2, Nearest), 1);
+		assert_eq!(sqrt_of_product(2, 3, Nearest), 2);
+		assert_eq!(sqrt_of_product(3, 4, Nearest), 3);
+		// sqrt(7) = 2.645... and sqrt(42) = 6.480...
+		assert_eq!(sqrt_of_product(1, 7, Nearest), 3);
+		assert_eq!(sqrt_of_product(6, 7, Down), 6);
+		assert_eq!(sqrt_of_product(6, 7, Up), 7);
+	}
+
+	#[test]
+	fn log2_and_log10_floor_work() {
+		assert_eq!(log2_floor(0), None);
+		assert_eq!(log10_floor(0), None);
+		assert_eq!(log2_floor(MAX), Some(127));
+		assert_eq!(log10_floor(MAX), Some(38));
+
+		let mut power = 1;
+		for i in 0..128 {
+			assert_eq!(log2_floor(power), Some(i));
+			if i > 0 {
+				assert_eq!(log2_floor(power - 1), Some(i - 1));
+			}
+			power = power.wrapping_shl(1);
+		}
+		let mut power = 1;
+		for i in 0..=38 {
+			assert_eq!(log10_floor(power), Some(i));
+			assert_eq!(log10_floor(power * 2), Some(i));
+			if i > 0 {
+				assert_eq!(log10_floor(power - 1), Some(i - 1));
+			}
+			power = power.saturating_mul(10);
+		}
+
+		const BITS: Option<u32> = log2_floor(1 << 100);
+		const DIGITS: Option<u32> = log10_floor(1_000_000_000_000_000_000);
+		assert_eq!(BITS, Some(100));
+		assert_eq!(DIGITS, Some(18));
+	}
+
+	#[test]
+	fn multiply_by_rational_with_remainder_works() {
+		let mul = multiply_by_rational_with_remainder;
+		assert_eq!(mul(10, 10, 3), Some((33, 1)));
+		assert_eq!(mul(10, 10, 5), Some((20, 0)));
+		assert_eq!(mul(MAX, MAX, MAX), Some((MAX, 0)));
+		assert_eq!(mul(MAX, MAX - 1, MAX - 2), None);
+		// MAX * 2 == 3 * 226854911280625642308916404954512140970 + 0
+		assert_eq!(mul(MAX, 2, 3), Some((226854911280625642308916404954512140970, 0)));
+		assert_eq!(mul(MAX, 5, 7), Some((243058833514956045330981862451263008182, 1)));
+
+		// Consistent with the rounding variant.
+		for (a, b, c) in [(7, 9, 4), (MAX, 3, 7), (MAX / 3, MAX / 5, MAX / 2), (1, 1, MAX)] {
+			let (q, r) = mul(a, b, c).unwrap();
+			assert!(r < c);
+			assert_eq!(multiply_by_rational_with_rounding(a, b, c, Down), Some(q));
+			assert_eq!(multiply_by_rational_with_rounding(a, b, c, Up), Some(q + (r > 0) as u128));
+		}
+	}
+
+	#[test]
+	#[should_panic(expected = "attempt to divide by zero")]
+	fn multiply_by_rational_with_remainder_panics_on_zero() {
+		multiply_by_rational_with_remainder(1, 1, 0);
+	}
+
+	#[test]
+	fn weighted_mean_works() {
+		assert_eq!(weighted_mean(&[]), None);
+		assert_eq!(weighted_mean(&[(5, 0), (7, 0)]), None);
+		assert_eq!(weighted_mean(&[(5, 1)]), Some(5));
+		assert_eq!(weighted_mean(&[(10, 1), (20, 1)]), Some(15));
+		assert_eq!(weighted_mean(&[(10, 3), (20, 1)]), Some(12));
+		// (1 * 1 + 2 * 1) / 2 == 1.5, rounded down.
+		assert_eq!(weighted_mean(&[(1, 1), (2, 1)]), Some(1));
+		assert_eq!(weighted_mean(&[(10, 0), (20, 5)]), Some(20));
+
+		// Products and sums that overflow a `u128`.
+		assert_eq!(weighted_mean(&[(MAX, MAX)]), Some(MAX));
+		assert_eq!(weighted_mean(&[(MAX, MAX / 2), (MAX - 2, MAX / 2)]), Some(MAX - 1));
+		assert_eq!(weighted_mean(&[(MAX, 1 << 100), (0, 1 << 100)]), Some(MAX / 2));
+
+		// The total weight must fit in a `u128`.
+		assert_eq!(weighted_mean(&[(1, MAX), (1, 1)]), None);
+	}
+
+	#[test]
+	fn gcd_works() {
+		assert_eq!(gcd(0, 0), 0);
+		assert_eq!(gcd(0, 9), 9);
+		assert_eq!(gcd(9, 0), 9);
+		assert_eq!(gcd(12, 18), 6);
+		assert_eq!(gcd(u128::MAX, u128::MAX), u128::MAX);
+		assert_eq!(gcd(1 << 127, 3 << 100), 1 << 100);
+		assert_eq!(gcd(u128::MAX, 1 << 127), 1);
+
+		// Consecutive Fibonacci numbers are the worst case for Euclid's algorithm.
+		let (mut a, mut b) = (1u128, 1u128);
+		while let Some(c) = a.checked_add(b) {
+			assert_eq!(gcd(a, b), 1);
+			if c <= u128::MAX >> 2 {
+				assert_eq!(gcd(c << 2, b << 2), 4);
+			}
+			(a, b) = (b, c);
+		}
+
+		assert_eq!(gcd(1, u128::MAX), 1);
+		assert_eq!(gcd(3, u128::MAX - 1), 1);
+	}
+
+	#[test]
+	fn stochastic_rounding_works() {
+		let mul = multiply_by_rational_with_stochastic_rounding;
+
+		// Exact results are never rounded.
+		assert_eq!(mul(10, 3, 5, 0), Some(6));
+		assert_eq!(mul(10, 3, 5, MAX), Some(6));
+
+		// 1 / 4 is rounded up for the lowest quarter of the entropy values.
+		assert_eq!(mul(1, 1, 4, 0), Some(1));
+		assert_eq!(mul(1, 1, 4, (1 << 126) - 1), Some(1));
+		assert_eq!(mul(1, 1, 4, 1 << 126), Some(0));
+		assert_eq!(mul(1, 1, 4, MAX), Some(0));
+		assert_eq!(mul(7, 1, 2, MAX / 2), Some(4));
+		assert_eq!(mul(7, 1, 2, MAX / 2 + 1), Some(3));
+
+		// Over evenly spread entropy values, the average is the exact result.
+		let samples = 1000u128;
+		let step = MAX / samples;
+		let sum: u128 = (0..samples).map(|i| mul(1000, 2, 3, i * step).unwrap()).sum();
+		assert_eq!(sum, 666 * samples + 667);
+
+		assert_eq!(mul(MAX, MAX, MAX, 0), Some(MAX));
+		assert_eq!(mul(MAX, 2, 1, 0), None);
+		assert_eq!(mul(MAX, MAX - 1, MAX - 1, 0), Some(MAX));
+	}
+}
@@ -0,0 +1,252 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Some helper functions to work with 256bit numbers. These mirror the ones in
+//! [`crate::helpers_128bit`] for values that do not fit in a `u128`, such as token amounts scaled
+//! by `10^18` multiplied by a price.
+
+use crate::Rounding;
+use primitive_types::U256;
+
+/// Returns the least significant 128 bits of a
+fn low_128(a: U256) -> U256 {
+	a & U256::from(u128::MAX)
+}
+
+/// Returns the most significant 128 bits of a
+fn high_128(a: U256) -> U256 {
+	a >> 128
+}
+
+/// Returns 2^256 - a (two's complement)
+fn neg256(a: U256) -> U256 {
+	(!a).overflowing_add(U256::one()).0
+}
+
+/// Returns 2^256 / a
+fn div256(a: U256) -> U256 {
+	(neg256(a) / a).overflowing_add(U256::one()).0
+}
+
+/// Returns 2^256 % a
+fn mod256(a: U256) -> U256 {
+	neg256(a) % a
+}
+
+/// A 512-bit unsigned integer, represented as two 256-bit halves.
+///
+/// Fields are ordered from the most significant, so the derived ordering is numeric.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Debug)]
+pub struct Double256 {
+	high: U256,
+	low: U256,
+}
+
+impl Double256 {
+	/// Narrow `self` into a `U256`, returning `None` if the high half is non-zero.
+	pub fn try_into_u256(self) -> Option<U256> {
+		if self.high.is_zero() {
+			Some(self.low)
+		} else {
+			None
+		}
+	}
+
+	/// Returns the `(high, low)` 256-bit halves of `self`.
+	pub fn into_parts(self) -> (U256, U256) {
+		(self.high, self.low)
+	}
+
+	/// The zero value.
+	pub fn zero() -> Self {
+		Self { high: U256::zero(), low: U256::zero() }
+	}
+
+	/// Return a `Double256` value representing the `scaled_value << 128`.
+	pub fn left_shift_128(scaled_value: U256) -> Self {
+		Self { high: scaled_value >> 128, low: scaled_value << 128 }
+	}
+
+	/// Construct a value from the lower 256 bits only, with the upper being zeroed.
+	pub fn from_low(low: U256) -> Self {
+		Self { high: U256::zero(), low }
+	}
+
+	/// Returns the same value ignoring anything in the high 256-bits.
+	pub fn low_part(self) -> Self {
+		Self { high: U256::zero(), ..self }
+	}
+
+	/// Returns a*b (in 512 bits)
+	pub fn product_of(a: U256, b: U256) -> Self {
+		// Split a and b into 128-bit halves, so that each partial product fits in 256 bits. See
+		// `helpers_128bit::Double128::product_of` for the derivation.
+		let (a_low, a_high) = (low_128(a), high_128(a));
+		let (b_low, b_high) = (low_128(b), high_128(b));
+		let (f, o, i, l) = (a_low * b_low, a_low * b_high, a_high * b_low, a_high * b_high);
+		let fl = Self { high: l, low: f };
+		let i = Self::left_shift_128(i);
+		let o = Self::left_shift_128(o);
+		fl.add(i).add(o)
+	}
+
+	/// Returns `self + b`, wrapping around on overflow of 512 bits.
+	pub fn add(self, b: Self) -> Self {
+		let (low, overflow) = self.low.overflowing_add(b.low);
+		let carry = if overflow { U256::one() } else { U256::zero() };
+		let high = self.high.overflowing_add(b.high).0.overflowing_add(carry).0;
+		Self { high, low }
+	}
+
+	/// Returns `(self / rhs, self % rhs)`.
+	///
+	/// Panics if `rhs` is zero.
+	pub fn div(mut self, rhs: U256) -> (Self, U256) {
+		if rhs == U256::one() {
+			return (self, U256::zero())
+		}
+
+		// Same approach as `helpers_128bit::Double128::div`:
+		// (a_high << 256 + a_low) / b = (a_low + a_high * r) / b + a_high * q
+		// where (q, r) = (2^256 / b, 2^256 % b).
+		let (q, r) = (div256(rhs), mod256(rhs));
+
+		let mut x = Self::zero();
+		while !self.high.is_zero() {
+			x = x.add(Self::product_of(self.high, q));
+			self = Self::product_of(self.high, r).add(self.low_part());
+		}
+
+		(x.add(Self::from_low(self.low / rhs)), self.low % rhs)
+	}
+}
+
+/// Returns `a * b / c` or `None` in the case of overflow, rounding according to `r`.
+///
+/// The 256-bit counterpart of [`crate::helpers_128bit::multiply_by_rational_with_rounding`].
+///
+/// Panics if `c` is zero.
+pub fn multiply_by_rational_with_rounding(a: U256, b: U256, c: U256, r: Rounding) -> Option<U256> {
+	if c.is_zero() {
+		panic!("attempt to divide by zero")
+	}
+	let (result, remainder) = Double256::product_of(a, b).div(c);
+	let result = result.try_into_u256()?;
+	if match r {
+		Rounding::Up => !remainder.is_zero(),
+		Rounding::Nearest => remainder > c / 2,
+		Rounding::ToEven => remainder > c / 2 || (remainder == c - remainder && result.bit(0)),
+		Rounding::Down => false,
+	} {
+		result.checked_add(U256::one())
+	} else {
+		Some(result)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{helpers_128bit, Rounding::*};
+
+	const MAX128: u128 = u128::MAX;
+
+	fn u256(x: u128) -> U256 {
+		U256::from(x)
+	}
+
+	#[test]
+	fn matches_128bit_version() {
+		let values = [0, 1, 2, 3, 7, 10, 1 << 64, MAX128 / 3, MAX128 - 1, MAX128];
+		for a in values {
+			for b in values {
+				for c in values.iter().filter(|c| **c != 0) {
+					for r in [Up, Down, Nearest, ToEven] {
+						let result =
+							multiply_by_rational_with_rounding(u256(a), u256(b), u256(*c), r)
+								.unwrap();
+						// Results that overflow a `u128` fit comfortably in a `U256`.
+						match helpers_128bit::multiply_by_rational_with_rounding(a, b, *c, r) {
+							Some(expected) => assert_eq!(result, u256(expected)),
+							None => assert!(result > u256(MAX128)),
+						}
+					}
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn works_with_256bit_operands() {
+		let max = U256::MAX;
+		let mul = multiply_by_rational_with_rounding;
+
+		assert_eq!(mul(max, max, max, Down), Some(max));
+		assert_eq!(mul(max, max - 1, max, Down), Some(max - 1));
+		assert_eq!(mul(max, 2.into(), max, Up), Some(2.into()));
+		assert_eq!(mul(max, 2.into(), 1.into(), Down), None);
+		assert_eq!(mul(max, max, max - 1, Down), None);
+
+		// 10^18 scaled amounts: (3 * 10^40) * (2 * 10^30) / 10^30 == 6 * 10^40.
+		let e = |n: u32| U256::from(10u8).pow(n.into());
+		assert_eq!(mul(e(40) * 3, e(30) * 2, e(30), Down), Some(e(40) * 6));
+
+		// max * 3 / 2 overflows, max / 2 rounds to the nearest.
+		assert_eq!(mul(max, 3.into(), 2.into(), Down), None);
+		assert_eq!(mul(max, 1.into(), 2.into(), Down), Some(max >> 1));
+		assert_eq!(mul(max, 1.into(), 2.into(), Nearest), Some(max >> 1));
+		assert_eq!(mul(max, 1.into(), 2.into(), ToEven), Some((max >> 1) + 1));
+		assert_eq!(mul(max - 2, 1.into(), 2.into(), ToEven), Some((max >> 1) - 1));
+		assert_eq!(mul(max, 1.into(), 2.into(), Up), Some((max >> 1) + 1));
+		assert_eq!(mul(max - 1, 1.into(), 2.into(), Up), Some(max >> 1));
+	}
+
+	#[test]
+	fn double256_div_works() {
+		let max = U256::MAX;
+		let (q, r) = Double256::product_of(max, max).div(max);
+		assert_eq!((q.try_into_u256(), r), (Some(max), U256::zero()));
+
+		let (q, r) = Double256::product_of(max, max).div(max - 1);
+		assert_eq!(q.try_into_u256(), None);
+		// max^2 = (max - 1) * (max + 2) + 1
+		assert_eq!(r, U256::one());
+
+		let (q, r) = Double256::product_of(max, 7.into()).div(7.into());
+		assert_eq!((q.try_into_u256(), r), (Some(max), U256::zero()));
+	}
+
+	#[test]
+	#[should_panic(expected = "attempt to divide by zero")]
+	fn divide_by_zero_panics() {
+		multiply_by_rational_with_rounding(1.into(), 1.into(), U256::zero(), Down);
+	}
+
+	#[test]
+	fn double256_add_and_ordering_work() {
+		let max = U256::MAX;
+		let sum = Double256::from_low(max).add(Double256::from_low(max));
+		assert_eq!(sum.into_parts(), (U256::one(), max - 1));
+		assert_eq!(sum.try_into_u256(), None);
+		assert_eq!(Double256::product_of(max, 2.into()), sum);
+
+		assert!(Double256::from_low(max) < sum);
+		assert!(Double256::left_shift_128(U256::one()) > Double256::from_low(u256(MAX128)));
+		assert!(Double256::product_of(max, max) > Double256::product_of(max, max - 1));
+		assert_eq!(Double256::zero().into_parts(), (U256::zero(), U256::zero()));
+	}
+}
@@ -36,13 +36,17 @@ macro_rules! assert_eq_error_rate {
 pub mod biguint;
 pub mod fixed_point;
 pub mod helpers_128bit;
+pub mod helpers_256bit;
 pub mod per_things;
 pub mod rational;
 pub mod traits;
+pub mod transcendental;
 
-pub use fixed_point::{FixedI128, FixedI64, FixedPointNumber, FixedPointOperand, FixedU128};
-pub use per_things::{InnerOf, PerThing, PerU16, Perbill, Percent, Permill, Perquintill, UpperOf};
-pub use rational::{Rational128, RationalInfinite};
+pub use fixed_point::{Ema, FixedI128, FixedI64, FixedPointNumber, FixedPointOperand, FixedU128};
+pub use per_things::{
+	InnerOf, PerThing, PerU16, Perbill, Percent, Permill, Perquintill, Rounding, UpperOf,
+};
+pub use rational::{Rational, Rational128, RationalInfinite};
 
 use sp_std::{cmp::Ordering, fmt::Debug, prelude::*};
 use traits::{BaseArithmetic, One, SaturatedConversion, Unsigned, Zero};
@@ -275,6 +279,83 @@ where
 	Ok(output_with_idx.into_iter().map(|(_, t)| t).collect())
 }
 
+/// Scale `input` proportionally, so that it sums up to exactly `P::one()`.
+///
+/// Unlike [`Normalizable::normalize`], which spreads the difference evenly, each element keeps its
+/// share of the total. Elements are first scaled and rounded down. The remaining parts, fewer than
+/// the number of elements, then go one by one to the elements with the largest rounding error,
+/// the earlier element winning ties. Hence the result only depends on the input and its order.
+///
+/// Returns an error if `input` is not empty but sums up to zero, or if its sum cannot fit in
+/// `UpperOf<P>`.
+pub fn normalize_proportional<P: PerThing>(input: &[P]) -> Result<Vec<P>, &'static str> {
+	if input.is_empty() {
+		return Ok(Vec::new())
+	}
+
+	let mut sum = <UpperOf<P>>::zero();
+	for p in input.iter() {
+		sum = sum
+			.checked_add(&p.deconstruct().into())
+			.ok_or("sum of input cannot fit in `UpperOf<P>`")?;
+	}
+	if sum.is_zero() {
+		return Err("cannot normalize an input summing up to zero")
+	}
+
+	// `p * ACCURACY` fits since `p <= ACCURACY`, and the scaled values add up to at most one.
+	let accuracy = <UpperOf<P>>::from(P::ACCURACY);
+	let mut dust = accuracy;
+	let mut output = Vec::with_capacity(input.len());
+	let mut remainders = Vec::with_capacity(input.len());
+	for (i, p) in input.iter().enumerate() {
+		let scaled = <UpperOf<P>>::from(p.deconstruct()) * accuracy;
+		let part = scaled / sum;
+		dust -= part;
+		output.push(part);
+		remainders.push((scaled % sum, i));
+	}
+
+	// the sort is stable, so equal remainders keep their original order.
+	remainders.sort_by(|a, b| b.0.cmp(&a.0));
+	for (_, i) in remainders.into_iter() {
+		if dust.is_zero() {
+			break
+		}
+		output[i] += One::one();
+		dust -= One::one();
+	}
+
+	Ok(output.into_iter().map(|x| P::from_parts(x.saturated_into())).collect())
+}
+
+/// Greatest common divisor of `a` and `b`, for any unsigned integer type. `gcd(a, 0) == a`.
+///
+/// See [`helpers_128bit::gcd`] for a faster `u128`-only version.
+pub fn gcd<T>(mut a: T, mut b: T) -> T
+where
+	T: BaseArithmetic + Unsigned + Copy,
+{
+	while !b.is_zero() {
+		let r = a % b;
+		a = b;
+		b = r;
+	}
+	a
+}
+
+/// Least common multiple of `a` and `b`, for any unsigned integer type, or `None` if it does not
+/// fit in `T`. The least common multiple of zero and any number is zero.
+pub fn lcm<T>(a: T, b: T) -> Option<T>
+where
+	T: BaseArithmetic + Unsigned + Copy,
+{
+	if a.is_zero() || b.is_zero() {
+		return Some(T::zero())
+	}
+	(a / gcd(a, b)).checked_mul(&b)
+}
+
 #[cfg(test)]
 mod normalize_tests {
 	use super::*;
@@ -356,6 +437,56 @@ mod normalize_tests {
 		);
 	}
 
+	#[test]
+	fn normalize_proportional_works() {
+		// the dust goes to the largest rounding error, and to the first element on ties.
+		assert_eq!(
+			normalize_proportional(&[Perbill::from_parts(333333333); 3]).unwrap(),
+			vec![
+				Perbill::from_parts(333333334),
+				Perbill::from_parts(333333333),
+				Perbill::from_parts(333333333),
+			]
+		);
+		assert_eq!(
+			normalize_proportional(&[
+				Percent::from_percent(30),
+				Percent::from_percent(50),
+				Percent::from_percent(30),
+			])
+			.unwrap(),
+			vec![Percent::from_percent(27), Percent::from_percent(46), Percent::from_percent(27)]
+		);
+
+		// proportions are kept, unlike with `normalize`.
+		assert_eq!(
+			normalize_proportional(&[
+				Perbill::from_percent(20),
+				Perbill::from_percent(15),
+				Perbill::from_percent(30),
+			])
+			.unwrap(),
+			vec![
+				Perbill::from_parts(307692308),
+				Perbill::from_parts(230769231),
+				Perbill::from_parts(461538461),
+			]
+		);
+
+		// already normalized input is returned as is.
+		let input = [Permill::from_parts(1), Permill::from_parts(999_999)];
+		assert_eq!(normalize_proportional(&input).unwrap(), input.to_vec());
+
+		// the sum is computed in the upper type.
+		assert_eq!(
+			normalize_proportional(&[PerU16::one(); 2]).unwrap(),
+			vec![PerU16::from_parts(32768), PerU16::from_parts(32767)]
+		);
+
+		assert_eq!(normalize_proportional::<Perbill>(&[]), Ok(vec![]));
+		assert!(normalize_proportional(&[Perbill::zero(); 2]).is_err());
+	}
+
 	#[test]
 	fn normalize_works_all_le() {
 		assert_eq!(normalize(vec![8u32, 9, 7, 10].as_ref(), 40).unwrap(), vec![10, 10, 10, 10]);
@@ -458,3 +589,36 @@ mod threshold_compare_tests {
 		assert_eq!(Saturating::saturating_pow(i32::MAX, 2), i32::MAX);
 	}
 }
+
+#[cfg(test)]
+mod gcd_lcm_tests {
+	use super::*;
+
+	#[test]
+	fn gcd_works() {
+		assert_eq!(gcd(0u8, 0), 0);
+		assert_eq!(gcd(0u16, 7), 7);
+		assert_eq!(gcd(12u32, 0), 12);
+		assert_eq!(gcd(12u64, 18), 6);
+		assert_eq!(gcd(7u128, 22), 1);
+		assert_eq!(gcd(u128::MAX, u128::MAX / 5), u128::MAX / 5);
+		for (a, b) in [(0, 0), (1, 0), (12, 18), (1 << 100, 3 << 90), (u128::MAX, u64::MAX as u128)]
+		{
+			assert_eq!(gcd(a, b), helpers_128bit::gcd(a, b));
+		}
+	}
+
+	#[test]
+	fn lcm_works() {
+		assert_eq!(lcm(0u32, 5), Some(0));
+		assert_eq!(lcm(4u32, 6), Some(12));
+		assert_eq!(lcm(7u8, 7), Some(7));
+		assert_eq!(lcm(16u8, 17), None);
+		assert_eq!(lcm(u64::MAX, u64::MAX), Some(u64::MAX));
+		assert_eq!(
+			lcm(u64::MAX as u128, u64::MAX as u128 - 1),
+			Some(u64::MAX as u128 * (u64::MAX as u128 - 1))
+		);
+		assert_eq!(lcm(u128::MAX, u128::MAX - 1), None);
+	}
+}
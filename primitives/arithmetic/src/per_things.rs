@@ -18,9 +18,12 @@
 #[cfg(feature = "std")]
 use serde::{Deserialize, Serialize};
 
-use crate::traits::{
-	BaseArithmetic, Bounded, CheckedAdd, CheckedMul, CheckedSub, One, SaturatedConversion,
-	Saturating, UniqueSaturatedInto, Unsigned, Zero,
+use crate::{
+	helpers_128bit,
+	traits::{
+		BaseArithmetic, Bounded, CheckedAdd, CheckedMul, CheckedSub, One, SaturatedConversion,
+		Saturating, UniqueSaturatedInto, Unsigned, Zero,
+	},
 };
 use codec::{CompactAs, Encode};
 use num_traits::{Pow, SaturatingAdd, SaturatingSub};
@@ -104,6 +107,63 @@ pub trait PerThing:
 		Self::from_rational::<Self::Upper>(p * p, q * q)
 	}
 
+	/// Return the square root of this value, rounded to the nearest part.
+	///
+	/// The result is at least `self`, since `self` is at most one.
+	fn sqrt(self) -> Self {
+		self.sqrt_with_rounding(Rounding::Nearest)
+	}
+
+	/// Return the square root of this value, rounded according to `rounding`.
+	///
+	/// This is `sqrt(parts * ACCURACY)` parts, computed exactly before rounding, so unlike a
+	/// round trip through a fixed point type no precision is lost.
+	fn sqrt_with_rounding(self, rounding: Rounding) -> Self {
+		let root = helpers_128bit::sqrt_of_product(
+			self.deconstruct().into(),
+			Self::ACCURACY.into(),
+			rounding,
+		);
+		// `parts <= ACCURACY`, so the root is at most `ACCURACY` as well.
+		Self::from_parts(root.saturated_into())
+	}
+
+	/// Raise `self` to the power of `exp` by repeated squaring, rounding every intermediate
+	/// product according to `rounding`.
+	///
+	/// `self` is at most one, so the result can never overflow and there is no need for checked
+	/// or saturating variants.
+	fn pow_with_rounding(self, exp: usize, rounding: Rounding) -> Self {
+		let accuracy = Self::Upper::from(Self::ACCURACY);
+		let mul = |a: Self, b: Self| -> Self {
+			// `Upper` fits `ACCURACY^2`, so the product cannot overflow.
+			let product = Self::Upper::from(a.deconstruct()) * Self::Upper::from(b.deconstruct());
+			let (parts, rem) = (product / accuracy, product % accuracy);
+			let round_up = match rounding {
+				Rounding::Up => !rem.is_zero(),
+				Rounding::Down => false,
+				Rounding::Nearest => rem > accuracy / 2.into(),
+				Rounding::ToEven =>
+					rem > accuracy / 2.into() ||
+						(rem == accuracy - rem && parts % 2.into() == One::one()),
+			};
+			let parts = if round_up { parts + One::one() } else { parts };
+			Self::from_parts(parts.unique_saturated_into())
+		};
+
+		let (mut base, mut exp, mut result) = (self, exp, Self::one());
+		while exp > 0 {
+			if exp % 2 == 1 {
+				result = mul(result, base);
+			}
+			exp /= 2;
+			if exp > 0 {
+				base = mul(base, base);
+			}
+		}
+		result
+	}
+
 	/// Return the part left when `self` is saturating-subtracted from `Self::one()`.
 	fn left_from_one(self) -> Self {
 		Self::one().saturating_sub(self)
@@ -167,6 +227,46 @@ pub trait PerThing:
 		overflow_prune_mul::<N, Self>(b, self.deconstruct(), Rounding::Up)
 	}
 
+	/// Linear interpolation between `from` and `to`, i.e. `from + self * (to - from)`, where `to`
+	/// may also be less than `from`.
+	///
+	/// The product is computed without overflow and rounded to the nearest whole number, like the
+	/// standard `Mul`, with an exact half rounding towards `from`.
+	///
+	/// ```rust
+	/// # use sp_arithmetic::{Percent, PerThing};
+	/// # fn main () {
+	/// assert_eq!(Percent::from_percent(25).lerp(100u64, 200), 125);
+	/// assert_eq!(Percent::from_percent(25).lerp(200u64, 100), 175);
+	///
+	/// // an exact half rounds towards `from`
+	/// assert_eq!(Percent::from_percent(50).lerp(0u64, 5), 2);
+	/// assert_eq!(Percent::from_percent(50).lerp(5u64, 0), 3);
+	/// # }
+	/// ```
+	fn lerp<N>(self, from: N, to: N) -> N
+	where
+		N: Clone
+			+ UniqueSaturatedInto<Self::Inner>
+			+ ops::Rem<N, Output = N>
+			+ ops::Div<N, Output = N>
+			+ ops::Mul<N, Output = N>
+			+ ops::Add<N, Output = N>
+			+ ops::Sub<N, Output = N>
+			+ PartialOrd
+			+ Unsigned,
+		Self::Inner: Into<N>,
+	{
+		// the delta is at most `|to - from|`, so neither operation can overflow.
+		if to >= from {
+			let delta = to - from.clone();
+			from + overflow_prune_mul::<N, Self>(delta, self.deconstruct(), Rounding::Nearest)
+		} else {
+			let delta = from.clone() - to;
+			from - overflow_prune_mul::<N, Self>(delta, self.deconstruct(), Rounding::Nearest)
+		}
+	}
+
 	/// Saturating multiplication by the reciprocal of `self`.	The result is rounded to the
 	/// nearest whole number and saturates at the numeric bounds instead of overflowing.
 	///
@@ -292,6 +392,35 @@ pub trait PerThing:
 			+ Unsigned,
 		Self::Inner: Into<N>;
 
+	/// Build this type from the ratio `p / q`, rounding according to `rounding`, in the same way
+	/// as [`helpers_128bit::multiply_by_rational_with_rounding`].
+	///
+	/// Returns `None` if `q` is zero or `p > q`, as the ratio is then not representable.
+	fn from_rational_with_rounding<N: Into<u128>>(p: N, q: N, rounding: Rounding) -> Option<Self> {
+		let (p, q) = (p.into(), q.into());
+		if q == 0 || p > q {
+			return None
+		}
+		let parts = helpers_128bit::multiply_by_rational_with_rounding(
+			p,
+			Self::ACCURACY.into(),
+			q,
+			rounding,
+		)?;
+		// `p <= q`, so `parts` is at most `ACCURACY`.
+		Self::Inner::try_from(parts).ok().map(Self::from_parts)
+	}
+
+	/// Convert from any other per-thing type, rounding according to `rounding` if `Self` cannot
+	/// represent `p` exactly.
+	///
+	/// Where the conversion is always exact, e.g. from `Percent` to `Perbill`, `From` is also
+	/// implemented, and `TryFrom` in the other direction.
+	fn from_per_thing<P: PerThing>(p: P, rounding: Rounding) -> Self {
+		Self::from_rational_with_rounding(p.deconstruct(), P::ACCURACY, rounding)
+			.expect("`p` is at most its accuracy, which is not zero; qed")
+	}
+
 	/// Same as `Self::from_rational`.
 	#[deprecated = "Use from_rational instead"]
 	fn from_rational_approximation<N>(p: N, q: N) -> Self
@@ -314,12 +443,20 @@ pub trait PerThing:
 
 /// The rounding method to use.
 ///
-/// `PerThing`s are unsigned so `Up` means towards infinity and `Down` means towards zero.
-/// `Nearest` will round an exact half down.
-enum Rounding {
+/// Operands are unsigned so `Up` means towards infinity and `Down` means towards zero.
+/// `Nearest` will round an exact half down, while `ToEven` rounds it to the even neighbour so that
+/// repeated rounding is not biased in either direction.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Rounding {
+	/// Round towards infinity.
 	Up,
+	/// Round towards zero.
 	Down,
+	/// Round to the nearest whole number, an exact half is rounded down.
 	Nearest,
+	/// Round to the nearest whole number, an exact half is rounded to the even one. Also known as
+	/// banker's rounding.
+	ToEven,
 }
 
 /// Saturating reciprocal multiplication. Compute `x / self`, saturating at the numeric
@@ -367,7 +504,8 @@ where
 /// to `x / denom * numer` for an accurate result.
 fn rational_mul_correction<N, P>(x: N, numer: P::Inner, denom: P::Inner, rounding: Rounding) -> N
 where
-	N: UniqueSaturatedInto<P::Inner>
+	N: Clone
+		+ UniqueSaturatedInto<P::Inner>
 		+ ops::Div<N, Output = N>
 		+ ops::Mul<N, Output = N>
 		+ ops::Add<N, Output = N>
@@ -379,7 +517,7 @@ where
 	let numer_upper = P::Upper::from(numer);
 	let denom_n: N = denom.into();
 	let denom_upper = P::Upper::from(denom);
-	let rem = x.rem(denom_n);
+	let rem = x.clone().rem(denom_n.clone());
 	// `rem` is less than `denom`, which fits in `P::Inner`.
 	let rem_inner = rem.saturated_into::<P::Inner>();
 	// `P::Upper` always fits `P::Inner::max_value().pow(2)`, thus it fits `rem * numer`.
@@ -405,6 +543,19 @@ where
 				rem_mul_div_inner += 1.into();
 			}
 		},
+		// Same as `Nearest`, except that an exact half is rounded up if that makes the result of
+		// `x / denom * numer + correction` even.
+		Rounding::ToEven => {
+			let frac = rem_mul_upper % denom_upper;
+			let half = frac == denom_upper - frac;
+			let two = N::one() + N::one();
+			let odd = (x / denom_n % two == N::one() && numer % 2.into() == One::one()) !=
+				(rem_mul_div_inner % 2.into() == One::one());
+			if frac > denom_upper / 2.into() || (half && odd) {
+				// `rem * numer / denom` is less than `numer`, so this will not overflow.
+				rem_mul_div_inner += 1.into();
+			}
+		},
 	}
 	rem_mul_div_inner.into()
 }
@@ -531,6 +682,19 @@ macro_rules! implement_per_thing {
 				Self(([x, 100][(x > 100) as usize] as $upper_type * $max as $upper_type / 100) as $type)
 			}
 
+			/// Converts `p / q` into `Self`, rounding down. Saturates to one if `p > q`, and treats a
+			/// zero `q` as one.
+			///
+			/// Unlike [`PerThing::from_rational`] this only accepts the inner type, so it can be
+			/// created at compile time. The exact quotient is rounded, so for `q` above the accuracy
+			/// it may be more precise than [`PerThing::from_rational`].
+			pub const fn from_rational_const(p: $type, q: $type) -> Self {
+				let q = [q, 1][(q == 0) as usize];
+				let p = [p, q][(p > q) as usize];
+				// `p <= q <= $type::max_value()`, so `p * $max` always fits in `$upper_type`.
+				Self((p as $upper_type * $max as $upper_type / q as $upper_type) as $type)
+			}
+
 			/// See [`PerThing::one`]
 			pub const fn one() -> Self {
 				Self::from_parts($max)
@@ -561,6 +725,16 @@ macro_rules! implement_per_thing {
 				PerThing::square(self)
 			}
 
+			/// See [`PerThing::sqrt`].
+			pub fn sqrt(self) -> Self {
+				PerThing::sqrt(self)
+			}
+
+			/// See [`PerThing::sqrt_with_rounding`].
+			pub fn sqrt_with_rounding(self, rounding: Rounding) -> Self {
+				PerThing::sqrt_with_rounding(self, rounding)
+			}
+
 			/// See [`PerThing::from_float`].
 			#[cfg(feature = "std")]
 			pub fn from_float(x: f64) -> Self {
@@ -588,6 +762,20 @@ macro_rules! implement_per_thing {
 				<Self as PerThing>::from_rational(p, q)
 			}
 
+			/// See [`PerThing::from_rational_with_rounding`].
+			pub fn from_rational_with_rounding<N: Into<u128>>(
+				p: N,
+				q: N,
+				rounding: Rounding,
+			) -> Option<Self> {
+				<Self as PerThing>::from_rational_with_rounding(p, q, rounding)
+			}
+
+			/// See [`PerThing::from_per_thing`].
+			pub fn from_per_thing<P: PerThing>(p: P, rounding: Rounding) -> Self {
+				<Self as PerThing>::from_per_thing(p, rounding)
+			}
+
 			/// See [`PerThing::mul_floor`].
 			pub fn mul_floor<N>(self, b: N) -> N
 				where
@@ -611,6 +799,17 @@ macro_rules! implement_per_thing {
 				PerThing::mul_ceil(self, b)
 			}
 
+			/// See [`PerThing::lerp`].
+			pub fn lerp<N>(self, from: N, to: N) -> N
+				where
+					N: Clone + UniqueSaturatedInto<$type> +
+						ops::Rem<N, Output=N> + ops::Div<N, Output=N> + ops::Mul<N, Output=N> +
+						ops::Add<N, Output=N> + ops::Sub<N, Output=N> + PartialOrd + Unsigned,
+					$type: Into<N>,
+			{
+				PerThing::lerp(self, from, to)
+			}
+
 			/// See [`PerThing::saturating_reciprocal_mul`].
 			pub fn saturating_reciprocal_mul<N>(self, b: N) -> N
 				where
@@ -1242,6 +1441,123 @@ macro_rules! implement_per_thing {
 				);
 			}
 
+			#[test]
+			fn from_rational_with_rounding_works() {
+				use super::Rounding::*;
+				assert_eq!($name::from_rational_with_rounding(1u32, 0, Down), None);
+				assert_eq!($name::from_rational_with_rounding(2u32, 1, Up), None);
+				assert_eq!($name::from_rational_with_rounding(0u32, 5, Up), Some($name::zero()));
+				assert_eq!($name::from_rational_with_rounding(5u32, 5, Down), Some($name::one()));
+
+				// `ACCURACY / 7` is never a whole number.
+				let down = $name::from_rational_with_rounding(1u32, 7, Down).unwrap();
+				let up = $name::from_rational_with_rounding(1u32, 7, Up).unwrap();
+				let nearest = $name::from_rational_with_rounding(1u32, 7, Nearest).unwrap();
+				assert_eq!(down, $name::from_rational(1u128, 7));
+				assert_eq!(up.deconstruct(), down.deconstruct() + 1);
+				assert_eq!(nearest, if 2 * ($max as u128 % 7) > 7 { up } else { down });
+
+				// Large inputs do not overflow.
+				let max = u128::max_value();
+				assert_eq!($name::from_rational_with_rounding(max - 1, max, Up), Some($name::one()));
+				assert_eq!(
+					$name::from_rational_with_rounding(max - 1, max, Down),
+					Some($name::from_parts($max - 1)),
+				);
+			}
+
+			#[test]
+			fn sqrt_works() {
+				use super::Rounding::*;
+				assert_eq!($name::zero().sqrt(), $name::zero());
+				assert_eq!($name::one().sqrt(), $name::one());
+
+				let accuracy = $max as u128;
+				for parts in [1, 2, $max / 4, $max / 3, $max / 2, $max - 1] {
+					let x = $name::from_parts(parts);
+					let square = parts as u128 * accuracy;
+					let down = x.sqrt_with_rounding(Down).deconstruct() as u128;
+					assert!(down * down <= square && (down + 1) * (down + 1) > square);
+					let up = x.sqrt_with_rounding(Up).deconstruct() as u128;
+					assert_eq!(up, if down * down == square { down } else { down + 1 });
+					// there is never an exact half, as `(down + 1/2)^2` is not a whole number.
+					let nearest = x.sqrt().deconstruct() as u128;
+					let above_half = 4 * square > (2 * down + 1) * (2 * down + 1);
+					assert_eq!(nearest, if above_half { up } else { down });
+					assert_eq!(x.sqrt_with_rounding(ToEven).deconstruct() as u128, nearest);
+					assert!(x.sqrt() >= x);
+				}
+			}
+
+			#[test]
+			fn lerp_works() {
+				let max = u128::max_value();
+				for (from, to) in [(0u128, 100u128), (100, 0), (7, 7), (0, max), (max, 0)] {
+					assert_eq!($name::zero().lerp(from, to), from);
+					assert_eq!($name::one().lerp(from, to), to);
+					let half = $name::from_rational(1u32, 2).lerp(from, to);
+					assert!(half >= from.min(to) && half <= from.max(to));
+				}
+
+				// exact when the distance is a multiple of the accuracy.
+				let x = $name::from_parts($max / 3);
+				let (low, high) = (10u128, 10 + 3 * $max as u128);
+				assert_eq!(x.lerp(low, high), low + 3 * x.deconstruct() as u128);
+				assert_eq!(x.lerp(high, low), high - 3 * x.deconstruct() as u128);
+
+				// large values do not overflow.
+				let (low, high) = (max - 3 * $max as u128, max);
+				assert_eq!(x.lerp(low, high), low + 3 * x.deconstruct() as u128);
+				assert_eq!(x.lerp(high, low), high - 3 * x.deconstruct() as u128);
+			}
+
+			#[test]
+			fn pow_with_rounding_works() {
+				use super::Rounding::*;
+				let x = $name::from_parts($max / 3);
+				for rounding in [Up, Down, Nearest] {
+					assert_eq!(x.pow_with_rounding(0, rounding), $name::one());
+					assert_eq!(x.pow_with_rounding(1, rounding), x);
+					assert_eq!($name::zero().pow_with_rounding(5, rounding), $name::zero());
+					assert_eq!($name::one().pow_with_rounding(5, rounding), $name::one());
+				}
+
+				// x^2 is a single multiplication, which `Mul` rounds down.
+				assert_eq!(x.pow_with_rounding(2, Down), x * x);
+				for n in 2..=8 {
+					let down = x.pow_with_rounding(n, Down);
+					let nearest = x.pow_with_rounding(n, Nearest);
+					let up = x.pow_with_rounding(n, Up);
+					assert!(down <= nearest && nearest <= up);
+					// With `x < 1/2`, rounding errors of earlier products shrink.
+					assert!(up.deconstruct() - down.deconstruct() <= 3);
+				}
+
+				// Cubing one part gives zero when rounding down, but one part when rounding up.
+				assert_eq!($name::from_parts(1).pow_with_rounding(3, Down), $name::zero());
+				assert_eq!($name::from_parts(1).pow_with_rounding(3, Up), $name::from_parts(1));
+
+				// Exact halves, which `Nearest` rounds down and `ToEven` to the even neighbour.
+				// With `ACCURACY = m * 2^k` for an odd `m`, `(1/2)^(k + 1)` is `m / 2` parts,
+				// and for an odd `k`, `(3/4)^((k + 1) / 2)` is `3^((k + 1) / 2) * m / 2` parts.
+				// All earlier products are exact.
+				let tie = |x: $name, n: usize| {
+					let down = x.pow_with_rounding(n, Down);
+					let up = x.pow_with_rounding(n, Up);
+					assert_eq!(up.deconstruct() - down.deconstruct(), 1);
+					assert_eq!(x.pow_with_rounding(n, Nearest), down);
+					let even = if down.deconstruct() % 2 == 0 { down } else { up };
+					assert_eq!(x.pow_with_rounding(n, ToEven), even);
+				};
+				let k = $max.trailing_zeros() as usize;
+				if k > 0 {
+					tie($name::from_parts($max / 2), k + 1);
+				}
+				if k > 1 && k % 2 == 1 {
+					tie($name::from_parts($max / 4 * 3), (k + 1) / 2);
+				}
+			}
+
 			#[test]
 			fn saturating_pow_works() {
 				// x^0 == 1
@@ -1413,6 +1729,27 @@ macro_rules! implement_per_thing {
 					),
 					1,
 				);
+				// An exact half is rounded so that `x / denom * numer + correction` is even.
+				for (x, numer, correction) in [(1, 1, 0), (3, 1, 1), (5, 3, 2), (7, 3, 1)] {
+					assert_eq!(
+						super::rational_mul_correction::<$type, $name>(
+							x,
+							numer,
+							2 as $type,
+							super::Rounding::ToEven,
+						),
+						correction,
+					);
+				}
+				assert_eq!(
+					super::rational_mul_correction::<$type, $name>(
+						7,
+						3,
+						4 as $type,
+						super::Rounding::ToEven,
+					),
+					2,
+				);
 			}
 
 			#[test]
@@ -1425,6 +1762,24 @@ macro_rules! implement_per_thing {
 
 				// deconstruct is also const, hence it can be called in const rhs.
 				const C5: bool = C1.deconstruct() == 0;
+
+				const C6: $name = $name::from_rational_const(1, 2);
+			}
+
+			#[test]
+			fn from_rational_const_works() {
+				let max = <$type>::max_value();
+				assert_eq!($name::from_rational_const(1, 2), $name::from_percent(50));
+				assert_eq!($name::from_rational_const(0, 7), $name::zero());
+				assert_eq!($name::from_rational_const(3, 3), $name::one());
+				assert_eq!($name::from_rational_const(5, 3), $name::one());
+				assert_eq!($name::from_rational_const(1, 0), $name::one());
+				assert_eq!($name::from_rational_const(max, max), $name::one());
+
+				// Same as `from_rational` whenever `q` does not exceed the accuracy.
+				for (p, q) in [(1, 3), (2, 3), (7, 10), (99, 100), (1, $max), ($max - 1, $max)] {
+					assert_eq!($name::from_rational_const(p, q), $name::from_rational(p, q));
+				}
 			}
 
 			#[test]
@@ -1627,3 +1982,102 @@ implement_per_thing_with_perthousand!(
 	u128,
 	"_Parts per Quintillion_",
 );
+
+/// Implement exact conversions from each of `$from` to each of the more precise `$to`, i.e. those
+/// whose accuracy is a multiple of the one of `$from`, and fallible ones in the other direction.
+macro_rules! implement_per_thing_conversions {
+	($( $from:ident => [ $( $to:ident ),+ ] ),+ $(,)?) => {
+		$( $(
+			impl From<$from> for $to {
+				fn from(p: $from) -> Self {
+					let factor = $to::ACCURACY / InnerOf::<$to>::from($from::ACCURACY);
+					Self::from_parts(InnerOf::<$to>::from(p.deconstruct()) * factor)
+				}
+			}
+
+			impl TryFrom<$to> for $from {
+				type Error = &'static str;
+
+				/// Fails if `p` cannot be represented exactly. See [`PerThing::from_per_thing`] to
+				/// round instead.
+				fn try_from(p: $to) -> Result<Self, Self::Error> {
+					let factor = $to::ACCURACY / InnerOf::<$to>::from($from::ACCURACY);
+					if p.deconstruct() % factor != 0 {
+						return Err("value is not representable without rounding")
+					}
+					let parts = (p.deconstruct() / factor)
+						.try_into()
+						.map_err(|_| "value is not representable without rounding")?;
+					Ok(Self::from_parts(parts))
+				}
+			}
+		)+ )+
+	};
+}
+
+implement_per_thing_conversions!(
+	Percent => [Permill, Perbill, Perquintill],
+	Permill => [Perbill, Perquintill],
+	Perbill => [Perquintill],
+);
+
+#[cfg(test)]
+mod conversion_tests {
+	use super::*;
+
+	#[test]
+	fn lossless_conversions_work() {
+		assert_eq!(Perbill::from(Percent::from_percent(37)), Perbill::from_percent(37));
+		assert_eq!(Perquintill::from(Percent::one()), Perquintill::one());
+		assert_eq!(Perbill::from(Permill::from_parts(1)), Perbill::from_parts(1_000));
+		assert_eq!(
+			Perquintill::from(Perbill::from_parts(123_456_789)),
+			Perquintill::from_parts(123_456_789_000_000_000)
+		);
+
+		assert_eq!(Percent::try_from(Perbill::from_percent(37)), Ok(Percent::from_percent(37)));
+		assert_eq!(Permill::try_from(Perquintill::one()), Ok(Permill::one()));
+		assert!(Percent::try_from(Perbill::from_parts(370_000_001)).is_err());
+		assert!(Perbill::try_from(Perquintill::from_parts(1)).is_err());
+
+		// every value survives the round trip.
+		for parts in 0..=100 {
+			let p = Percent::from_parts(parts);
+			assert_eq!(Percent::try_from(Perquintill::from(p)), Ok(p));
+		}
+	}
+
+	#[test]
+	fn from_per_thing_works() {
+		use Rounding::*;
+		let p = Perbill::from_parts(374_999_999);
+		assert_eq!(Percent::from_per_thing(p, Down), Percent::from_percent(37));
+		assert_eq!(Percent::from_per_thing(p, Up), Percent::from_percent(38));
+		assert_eq!(Percent::from_per_thing(p, Nearest), Percent::from_percent(37));
+		let half = Perbill::from_parts(25_000_000);
+		assert_eq!(Percent::from_per_thing(half, ToEven), Percent::from_percent(2));
+		assert_eq!(Percent::from_per_thing(half, Nearest), Percent::from_percent(2));
+		assert_eq!(Percent::from_per_thing(half, Up), Percent::from_percent(3));
+
+		// also between types whose accuracies do not divide each other.
+		assert_eq!(
+			PerU16::from_per_thing(Percent::from_percent(50), Down),
+			PerU16::from_parts(32767)
+		);
+		assert_eq!(
+			PerU16::from_per_thing(Percent::from_percent(50), Up),
+			PerU16::from_parts(32768)
+		);
+		assert_eq!(Perbill::from_per_thing(PerU16::one(), Down), Perbill::one());
+
+		// exact conversions agree with `From`.
+		for rounding in [Up, Down, Nearest, ToEven] {
+			let p = Permill::from_parts(123_456);
+			assert_eq!(Perquintill::from_per_thing(p, rounding), Perquintill::from(p));
+			assert_eq!(
+				Perquintill::from_per_thing(Perquintill::one(), rounding),
+				Perquintill::one()
+			);
+		}
+	}
+}
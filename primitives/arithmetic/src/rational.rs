@@ -17,7 +17,8 @@
 
 use crate::{biguint::BigUint, helpers_128bit};
 use num_traits::{Bounded, One, Zero};
-use sp_std::{cmp::Ordering, prelude::*};
+use primitive_types::U256;
+use sp_std::{cmp::Ordering, ops, prelude::*};
 
 /// A wrapper for any rational number with infinitely large numerator and denominator.
 ///
@@ -212,6 +213,25 @@ impl Rational128 {
 			.ok_or("overflow while subtracting numerators")?;
 		Ok(Self(n, self_scaled.1))
 	}
+
+	/// Build a `Rational128` from `n / d`, used by the arithmetic operators.
+	///
+	/// This is the closest fraction whose parts both fit in a `u128`, as in
+	/// [`best_approximation`]. It is exact and reduced if the reduced `n / d` fits, and otherwise
+	/// off by at most `2^-127` in relative terms if `n / d` is at least one, and by less than
+	/// `2^-128` in absolute terms below that. Saturates at `max_value()` if `n / d` is too large,
+	/// or if `d` is zero.
+	fn from_u256(n: U256, d: U256) -> Self {
+		// Any `n / d` above `u128::MAX` is approximated by `u128::MAX / 1`.
+		approximate(n, d, U256::from(u128::MAX))
+			.map(|(n, d)| Self(n.low_u128(), d.low_u128()))
+			.unwrap_or_else(Bounded::max_value)
+	}
+
+	/// The numerator and denominator as `U256`, so that products of two of them do not overflow.
+	fn to_u256(self) -> (U256, U256) {
+		(U256::from(self.0), U256::from(self.1))
+	}
 }
 
 impl Bounded for Rational128 {
@@ -269,6 +289,355 @@ impl PartialEq for Rational128 {
 	}
 }
 
+// The operators below are exact as long as the reduced result fits in 128 bits, and lose precision
+// otherwise. See `Rational128::from_u256`.
+
+impl ops::Add for Rational128 {
+	type Output = Self;
+
+	/// Adds two rationals, saturating at `max_value()`. The result is reduced.
+	fn add(self, other: Self) -> Self {
+		let ((a, b), (c, d)) = (self.to_u256(), other.to_u256());
+		// Each product is below `2^256`, but their sum may not be. Halving all terms keeps the
+		// value, at the cost of a bit of precision.
+		let (n, d) = match (a * d).checked_add(c * b) {
+			Some(n) => (n, b * d),
+			None => (((a * d) >> 1) + ((c * b) >> 1), (b * d) >> 1),
+		};
+		Self::from_u256(n, d)
+	}
+}
+
+impl ops::Sub for Rational128 {
+	type Output = Self;
+
+	/// Subtracts two rationals, saturating at zero. The result is reduced.
+	fn sub(self, other: Self) -> Self {
+		if self <= other {
+			return Self::zero()
+		}
+		let ((a, b), (c, d)) = (self.to_u256(), other.to_u256());
+		Self::from_u256(a * d - c * b, b * d)
+	}
+}
+
+impl ops::Mul for Rational128 {
+	type Output = Self;
+
+	/// Multiplies two rationals, saturating at `max_value()`. The result is reduced.
+	fn mul(self, other: Self) -> Self {
+		let ((a, b), (c, d)) = (self.to_u256(), other.to_u256());
+		Self::from_u256(a * c, b * d)
+	}
+}
+
+impl ops::Div for Rational128 {
+	type Output = Self;
+
+	/// Divides two rationals, saturating at `max_value()`. The result is reduced.
+	///
+	/// Panics if `other` is zero.
+	fn div(self, other: Self) -> Self {
+		if other.is_zero() {
+			panic!("attempt to divide by zero")
+		}
+		let ((a, b), (c, d)) = (self.to_u256(), other.to_u256());
+		Self::from_u256(a * d, b * c)
+	}
+}
+
+impl Zero for Rational128 {
+	fn zero() -> Self {
+		Self::zero()
+	}
+
+	fn is_zero(&self) -> bool {
+		self.is_zero()
+	}
+}
+
+impl One for Rational128 {
+	fn one() -> Self {
+		Self::one()
+	}
+}
+
+impl sp_std::iter::Sum for Rational128 {
+	fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+		iter.fold(Self::zero(), |acc, x| acc + x)
+	}
+}
+
+/// Returns the fraction closest to `n / d` whose numerator and denominator are both at most
+/// `limit`, reduced.
+///
+/// This walks the continued fraction expansion of `n / d`: the result is either the last
+/// convergent within `limit`, or the closest semiconvergent after it, preferring the smaller
+/// denominator on a tie. It is exact whenever the reduced `n / d` fits. Use a `limit` of
+/// `u64::MAX as u128` to store a ratio in a [`Rational<u64>`] without simply truncating it.
+///
+/// Returns `None` if `d` or `limit` is zero.
+pub fn best_approximation(n: u128, d: u128, limit: u128) -> Option<(u128, u128)> {
+	approximate(n, d, limit)
+}
+
+/// [`best_approximation`] for any [`RationalInner`], which `Rational128::from_u256` uses to bring
+/// a ratio of two `U256` values back to 128 bits.
+fn approximate<N: RationalInner + Into<U256>>(n: N, d: N, limit: N) -> Option<(N, N)> {
+	let (zero, one) = (N::from(0), N::from(1));
+	if d == zero || limit == zero {
+		return None
+	}
+	let (n0, d0) = (n, d);
+	let (mut n, mut d) = (n, d);
+	// `t * x1 + x2`, or `None` if it overflows.
+	let mul_add = |t: N, x1: N, x2: N| t.checked_mul(x1).and_then(|x| x.checked_add(x2));
+	// The last two convergents, starting with the conventional `0 / 1` and `1 / 0`.
+	let (mut h2, mut k2, mut h1, mut k1) = (zero, one, one, zero);
+	loop {
+		let a = n / d;
+		match (mul_add(a, h1, h2), mul_add(a, k1, k2)) {
+			(Some(h), Some(k)) if h <= limit && k <= limit => {
+				h2 = h1;
+				k2 = k1;
+				h1 = h;
+				k1 = k;
+				let r = n % d;
+				if r == zero {
+					return Some((h1, k1))
+				}
+				n = d;
+				d = r;
+			},
+			_ => {
+				// The largest `t < a` for which the semiconvergent `(t * h1 + h2) / (t * k1 + k2)`
+				// is within `limit`. `h2` and `k2` are, as they are a previous convergent, and
+				// `h1` and `k1` are not both zero.
+				let bound = |x1: N, x2: N| match x1 == zero {
+					true => None,
+					false => limit.checked_sub(x2).map(|room| room / x1),
+				};
+				let t = [bound(h1, h2), bound(k1, k2)].into_iter().flatten().min().unwrap_or(zero);
+				if t == zero {
+					return Some((h1, k1))
+				}
+				// Both parts are within `limit`, so this does not overflow.
+				let semi = (mul_add(t, h1, h2)?, mul_add(t, k1, k2)?);
+				if k1 == zero {
+					// `h1 / k1` is still the conventional `1 / 0`.
+					return Some(semi)
+				}
+				// Compare `|h1 / k1 - n0 / d0|` with `|semi - n0 / d0|`, without dividing.
+				let big = |x: N| {
+					let x: U256 = x.into();
+					(BigUint::from((x >> 128).low_u128()) << 128) + BigUint::from(x.low_u128())
+				};
+				let distance = |(h, k): (N, N), other_k: N| {
+					let error = big(h).mul(&big(d0)).abs_diff(&big(n0).mul(&big(k)));
+					error.mul(&big(other_k))
+				};
+				return if distance(semi, k1) < distance((h1, k1), semi.1) {
+					Some(semi)
+				} else {
+					Some((h1, k1))
+				}
+			},
+		}
+	}
+}
+
+/// Unsigned integer types that can be used as the numerator and denominator of a [`Rational`].
+///
+/// Zero and one are built through `From<u8>`, since `U256` does not implement the `num_traits`
+/// counterparts.
+pub trait RationalInner:
+	Copy + Ord + sp_std::fmt::Debug + From<u8> + ops::Div<Output = Self> + ops::Rem<Output = Self>
+{
+	/// Checked addition, returning `None` on overflow.
+	fn checked_add(self, other: Self) -> Option<Self>;
+
+	/// Checked subtraction, returning `None` on underflow.
+	fn checked_sub(self, other: Self) -> Option<Self>;
+
+	/// Checked multiplication, returning `None` on overflow.
+	fn checked_mul(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! impl_rational_inner {
+	($($type:ty),+) => {
+		$(impl RationalInner for $type {
+			fn checked_add(self, other: Self) -> Option<Self> {
+				<$type>::checked_add(self, other)
+			}
+
+			fn checked_sub(self, other: Self) -> Option<Self> {
+				<$type>::checked_sub(self, other)
+			}
+
+			fn checked_mul(self, other: Self) -> Option<Self> {
+				<$type>::checked_mul(self, other)
+			}
+		})*
+	}
+}
+impl_rational_inner!(u64, u128, U256);
+
+/// Greatest common divisor of `a` and `b`, with `gcd(a, 0) == a`.
+fn gcd_of<N: RationalInner>(mut a: N, mut b: N) -> N {
+	while b != N::from(0) {
+		let r = a % b;
+		a = b;
+		b = r;
+	}
+	a
+}
+
+/// A rational number with a numerator and denominator of any [`RationalInner`] type, such as
+/// `u64`, `u128` or `U256`.
+///
+/// Unlike [`Rational128`], results of arithmetic operations are always reduced, and the
+/// denominator is never zero.
+#[derive(Clone, Copy, Debug)]
+pub struct Rational<N>(N, N);
+
+impl<N: RationalInner> Rational<N> {
+	/// Zero.
+	pub fn zero() -> Self {
+		Self(N::from(0), N::from(1))
+	}
+
+	/// One.
+	pub fn one() -> Self {
+		Self(N::from(1), N::from(1))
+	}
+
+	/// If it is zero or not.
+	pub fn is_zero(&self) -> bool {
+		self.0 == N::from(0)
+	}
+
+	/// Build from a raw `n/d`. A zero denominator is replaced by one.
+	pub fn from(n: N, d: N) -> Self {
+		Self(n, d.max(N::from(1)))
+	}
+
+	/// Return the numerator.
+	pub fn n(&self) -> N {
+		self.0
+	}
+
+	/// Return the denominator.
+	pub fn d(&self) -> N {
+		self.1
+	}
+
+	/// Return the equivalent fraction with the numerator and denominator divided by their
+	/// greatest common divisor.
+	pub fn reduce(self) -> Self {
+		let g = gcd_of(self.0, self.1);
+		Self(self.0 / g, self.1 / g)
+	}
+
+	/// Get the least common multiple of the denominators of `self` and `other`.
+	pub fn lcm(&self, other: &Self) -> Result<N, &'static str> {
+		let g = gcd_of(self.1, other.1);
+		(self.1 / g).checked_mul(other.1).ok_or("least common multiple does not fit")
+	}
+
+	/// Addition over the least common multiple of the denominators.
+	///
+	/// Overflow might happen during any of the steps. Error is returned in such cases.
+	pub fn checked_add(self, other: Self) -> Result<Self, &'static str> {
+		let (a, b, d) = self.scale_to_common_den(other)?;
+		let n = a.checked_add(b).ok_or("overflow while adding numerators")?;
+		Ok(Self(n, d).reduce())
+	}
+
+	/// Subtraction over the least common multiple of the denominators.
+	///
+	/// Overflow might happen during any of the steps, and the result cannot be negative. Error
+	/// is returned in such cases.
+	pub fn checked_sub(self, other: Self) -> Result<Self, &'static str> {
+		let (a, b, d) = self.scale_to_common_den(other)?;
+		let n = a.checked_sub(b).ok_or("overflow while subtracting numerators")?;
+		Ok(Self(n, d).reduce())
+	}
+
+	/// Multiplication. Common factors are cancelled before multiplying, so this only fails if
+	/// the reduced result does not fit.
+	pub fn checked_mul(self, other: Self) -> Result<Self, &'static str> {
+		// With both operands reduced, cancelling the cross factors reduces the product.
+		let (this, other) = (self.reduce(), other.reduce());
+		let g1 = gcd_of(this.0, other.1);
+		let g2 = gcd_of(other.0, this.1);
+		let n = (this.0 / g1).checked_mul(other.0 / g2).ok_or("overflow while multiplying")?;
+		let d = (this.1 / g2).checked_mul(other.1 / g1).ok_or("overflow while multiplying")?;
+		Ok(Self(n, d))
+	}
+
+	/// Division, as multiplication by the reciprocal of `other`.
+	pub fn checked_div(self, other: Self) -> Result<Self, &'static str> {
+		if other.is_zero() {
+			return Err("division by zero")
+		}
+		self.checked_mul(Self(other.1, other.0))
+	}
+
+	/// Returns the numerators of `self` and `other` scaled to the least common multiple of their
+	/// denominators, along with that multiple.
+	fn scale_to_common_den(self, other: Self) -> Result<(N, N, N), &'static str> {
+		let g = gcd_of(self.1, other.1);
+		let (self_factor, other_factor) = (other.1 / g, self.1 / g);
+		let overflow = "failed to scale to denominator";
+		let d = self.1.checked_mul(self_factor).ok_or(overflow)?;
+		let a = self.0.checked_mul(self_factor).ok_or(overflow)?;
+		let b = other.0.checked_mul(other_factor).ok_or(overflow)?;
+		Ok((a, b, d))
+	}
+}
+
+impl<N: RationalInner> Ord for Rational<N> {
+	fn cmp(&self, other: &Self) -> Ordering {
+		// Compare the continued fraction expansions term by term, i.e. the integer parts first
+		// and then the reciprocals of the remainders, which never overflows.
+		let (mut a, mut b, mut c, mut d) = (self.0, self.1, other.0, other.1);
+		loop {
+			match (a / b).cmp(&(c / d)) {
+				Ordering::Equal => {},
+				ordering => return ordering,
+			}
+			let (r1, r2) = (a % b, c % d);
+			match (r1 == N::from(0), r2 == N::from(0)) {
+				(true, true) => return Ordering::Equal,
+				(true, false) => return Ordering::Less,
+				(false, true) => return Ordering::Greater,
+				(false, false) => {
+					// `r1 / b cmp r2 / d` is the same as `d / r2 cmp b / r1`.
+					let (next_c, next_d) = (b, r1);
+					a = d;
+					b = r2;
+					c = next_c;
+					d = next_d;
+				},
+			}
+		}
+	}
+}
+
+impl<N: RationalInner> PartialOrd for Rational<N> {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl<N: RationalInner> PartialEq for Rational<N> {
+	fn eq(&self, other: &Self) -> bool {
+		self.cmp(other) == Ordering::Equal
+	}
+}
+
+impl<N: RationalInner> Eq for Rational<N> {}
+
 #[cfg(test)]
 mod tests {
 	use super::{helpers_128bit::*, *};
@@ -392,6 +761,56 @@ mod tests {
 		assert_eq!(r(1, 10).checked_sub(r(2, 10)), Err("overflow while subtracting numerators"));
 	}
 
+	#[test]
+	fn operators_work() {
+		let parts = |x: Rational128| (x.n(), x.d());
+
+		// Results are exact and reduced when they fit.
+		assert_eq!(parts(r(3, 10) + r(1, 10)), (2, 5));
+		assert_eq!(parts(r(3, 10) + r(3, 7)), (51, 70));
+		assert_eq!(parts(r(3, 10) - r(1, 10)), (1, 5));
+		assert_eq!(parts(r(3, 10) * r(5, 7)), (3, 14));
+		assert_eq!(parts(r(3, 10) / r(9, 20)), (2, 3));
+		assert_eq!(parts(r(0, 10) + r(0, 7)), (0, 1));
+
+		// Where `checked_add` fails, the operators still give the exact result if it fits...
+		assert_eq!(
+			r(1, MAX128).checked_add(r(1, MAX128 - 1)),
+			Err("failed to scale to denominator"),
+		);
+		assert_eq!(parts(r(MAX64, MAX128) + r(MAX64, MAX128)), (2, u64::MAX as u128 + 2));
+		// ... or an approximation that does.
+		let sum = r(1, MAX128) + r(1, MAX128 - 1);
+		assert_eq!(parts(sum), (1, MAX128 / 2));
+		let product = r(MAX128 - 1, MAX128) * r(MAX128 - 2, MAX128 - 1);
+		assert_eq!(product, r(MAX128 - 2, MAX128));
+		// A small denominator does not lose precision: this is `2.2685...e38`, rounded to the
+		// nearest integer.
+		let product = r(MAX128, 3 * (1 << 63) + 5) * r(MAX128, (1 << 64) + 3);
+		assert_eq!(parts(product), (226_854_911_280_625_642_231_030_152_198_849_589_722, 1));
+
+		// Saturation.
+		assert_eq!(r(MAX128, 1) + r(1, 1), Rational128::max_value());
+		assert_eq!(r(MAX128, 1) * r(3, 2), Rational128::max_value());
+		assert_eq!(r(MAX128, 2) * r(2, 1), r(MAX128, 1));
+		assert_eq!(r(1, 1) / r(1, MAX128), r(MAX128, 1));
+		assert_eq!(r(2, 1) / r(1, MAX128), Rational128::max_value());
+		assert_eq!(r(1, 3) - r(1, 2), Rational128::zero());
+		assert_eq!(r(MAX128, MAX128) + r(MAX128, MAX128), r(2, 1));
+
+		// Generic code.
+		let sum: Rational128 = [r(1, 2), r(1, 3), r(1, 6)].into_iter().sum();
+		assert_eq!(parts(sum), (1, 1));
+		assert_eq!(<Rational128 as One>::one(), r(3, 3));
+		assert!(<Rational128 as Zero>::is_zero(&(r(1, 4) - r(2, 8))));
+	}
+
+	#[test]
+	#[should_panic(expected = "attempt to divide by zero")]
+	fn division_by_zero_panics() {
+		let _ = r(1, 2) / r(0, 5);
+	}
+
 	#[test]
 	fn ordering_and_eq_works() {
 		assert!(r(1, 2) > r(1, 3));
@@ -476,4 +895,132 @@ mod tests {
 			Ok(2596149632101417846585204209223679)
 		);
 	}
+
+	fn q<N: RationalInner>(n: N, d: N) -> Rational<N> {
+		Rational::from(n, d)
+	}
+
+	#[test]
+	fn generic_rational_ordering_works() {
+		assert!(q(1u64, 2) > q(1, 3));
+		assert!(q(1u64, 2) == q(2, 4));
+		assert!(q(0u64, 5) == q(0, 7));
+		assert!(q(0u64, 5) < q(1, u64::MAX));
+		assert!(q(u64::MAX, u64::MAX - 1) < q(u64::MAX - 1, u64::MAX - 2));
+		assert!(q(MAX128, MAX128 - 1) < q(MAX128 - 1, MAX128 - 2));
+		assert!(q(MAX128 - 1, MAX128) < q(MAX128, MAX128));
+		assert!(q(MAX128 - 2, MAX128 - 1) < q(MAX128 - 1, MAX128));
+
+		let max = U256::max_value();
+		assert!(q(max, max - 1) < q(max - 1, max - 2));
+		assert!(q(max - 1, max) > q(max - 2, max - 1));
+		assert!(q(max, U256::from(2u8)) == q(max, U256::from(2u8)));
+
+		// Matches the big-int comparison of `Rational128`.
+		let values = [0, 1, 2, 3, 7, 10, MAX64, MAX64_2, MAX128 / 3, MAX128 - 1, MAX128];
+		for a in values {
+			for b in values.iter().filter(|b| **b != 0) {
+				for c in values {
+					for d in values.iter().filter(|d| **d != 0) {
+						assert_eq!(q(a, *b).cmp(&q(c, *d)), r(a, *b).cmp(&r(c, *d)));
+					}
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn generic_rational_arithmetic_works() {
+		assert_eq!(q(0u64, 0).d(), 1);
+		assert_eq!(q(6u64, 8).reduce().n(), 3);
+		assert_eq!(q(6u64, 8).reduce().d(), 4);
+		assert_eq!(q(3u64, 10).lcm(&q(4, 15)), Ok(30));
+
+		let sum = q(3u64, 10).checked_add(q(3, 7)).unwrap();
+		assert_eq!((sum.n(), sum.d()), (51, 70));
+		let sum = q(3u128, 10).checked_add(q(1, 10)).unwrap();
+		assert_eq!((sum.n(), sum.d()), (2, 5));
+		let difference = q(6u128, 10).checked_sub(q(3, 7)).unwrap();
+		assert_eq!((difference.n(), difference.d()), (6, 35));
+		assert_eq!(
+			q(1u128, 10).checked_sub(q(2, 10)),
+			Err("overflow while subtracting numerators")
+		);
+		assert_eq!(
+			q(1u64, u64::MAX).checked_add(q(1, u64::MAX - 1)),
+			Err("failed to scale to denominator")
+		);
+		assert_eq!(q(u64::MAX, 1).checked_add(q(1, 1)), Err("overflow while adding numerators"));
+
+		// Common factors cancel before multiplying.
+		let product = q(MAX128, 3).checked_mul(q(3, MAX128)).unwrap();
+		assert_eq!((product.n(), product.d()), (1, 1));
+		assert_eq!(q(MAX128, 1).checked_mul(q(2, 1)), Err("overflow while multiplying"));
+		let quotient = q(2u64, 3).checked_div(q(4, 9)).unwrap();
+		assert_eq!((quotient.n(), quotient.d()), (3, 2));
+		assert_eq!(q(2u64, 3).checked_div(q(0, 9)), Err("division by zero"));
+
+		// Unreduced operands still give reduced results, and their common factors don't overflow.
+		let product = q(2u64, 4).checked_mul(q(1, 1)).unwrap();
+		assert_eq!((product.n(), product.d()), (1, 2));
+		let quotient = q(6u64, 9).checked_div(q(10, 15)).unwrap();
+		assert_eq!((quotient.n(), quotient.d()), (1, 1));
+		let product = q(MAX128 - 1, MAX128 - 1).checked_mul(q(MAX128, 2)).unwrap();
+		assert_eq!((product.n(), product.d()), (MAX128, 2));
+		let product = q(0u64, 7).checked_mul(q(3, 5)).unwrap();
+		assert_eq!((product.n(), product.d()), (0, 1));
+
+		// The same fractions that overflow a `u128` fit in a `U256`.
+		let (a, b) = (U256::from(MAX128), U256::from(MAX128 - 1));
+		let sum = q(U256::one(), a).checked_add(q(U256::one(), b)).unwrap();
+		assert_eq!(sum, q(a + b, a * b));
+	}
+
+	#[test]
+	fn best_approximation_works() {
+		let pi = (314_159_265_358_979_323_846, 100_000_000_000_000_000_000);
+		assert_eq!(best_approximation(pi.0, pi.1, 1000), Some((355, 113)));
+		assert_eq!(best_approximation(pi.0, pi.1, 100), Some((22, 7)));
+		assert_eq!(best_approximation(pi.0, pi.1, 10), Some((3, 1)));
+
+		// exact if the reduced fraction fits.
+		assert_eq!(best_approximation(6, 8, 4), Some((3, 4)));
+		assert_eq!(best_approximation(0, 8, 4), Some((0, 1)));
+		assert_eq!(best_approximation(MAX128, MAX128, 1), Some((1, 1)));
+
+		// bounded by the numerator as well as the denominator.
+		assert_eq!(best_approximation(MAX128, 1, 1000), Some((1000, 1)));
+		assert_eq!(best_approximation(1, MAX128, 1000), Some((0, 1)));
+		let max64 = u64::MAX as u128;
+		assert_eq!(best_approximation(MAX128, MAX128 - 1, max64), Some((1, 1)));
+		assert_eq!(best_approximation(max64 + 1, max64 + 2, max64), Some((max64 - 1, max64)));
+
+		assert_eq!(best_approximation(1, 0, 10), None);
+		assert_eq!(best_approximation(1, 2, 0), None);
+	}
+
+	#[test]
+	fn best_approximation_matches_exhaustive_search() {
+		use rand::{rngs::StdRng, Rng, SeedableRng};
+		let mut rng = StdRng::seed_from_u64(0x9e37_79b9_7f4a_7c15);
+		for _ in 0..2000 {
+			let n: u128 = rng.gen_range(0, 1_000_000);
+			let d: u128 = rng.gen_range(1, 1_000_001);
+			let limit: u128 = rng.gen_range(1, 41);
+			// the closest fraction, preferring smaller denominators, among all `p / q` in range.
+			let mut best = (0, 1);
+			let error = |p: u128, q: u128| (p * d).abs_diff(n * q);
+			for q in 1..=limit {
+				let p = ((n * q + d / 2) / d).min(limit);
+				for p in [p.saturating_sub(1), p, (p + 1).min(limit)] {
+					if error(p, q) * best.1 < error(best.0, best.1) * q {
+						best = (p, q);
+					}
+				}
+			}
+			let g = helpers_128bit::gcd(best.0, best.1);
+			let expected = Some((best.0 / g, best.1 / g));
+			assert_eq!(best_approximation(n, d, limit), expected, "{}/{} within {}", n, d, limit);
+		}
+	}
 }
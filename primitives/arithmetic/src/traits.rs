@@ -17,6 +17,7 @@
 
 //! Primitive traits for the runtime arithmetic.
 
+use crate::Rounding;
 use codec::HasCompact;
 pub use integer_sqrt::IntegerSquareRoot;
 pub use num_traits::{
@@ -179,6 +180,29 @@ impl<T: Bounded + Sized, S: TryInto<T> + Sized> UniqueSaturatedInto<T> for S {
 	}
 }
 
+/// Just like `From` except that values out of the range of `Self` saturate to its closest bound.
+///
+/// Unlike [`UniqueSaturatedFrom`], values below the range of `Self` saturate to its minimum, and
+/// the conversion may round, e.g. between fixed point numbers of different precision.
+pub trait SaturatingFrom<T: Sized>: Sized {
+	/// Convert from a value of `T` into the closest instance of `Self`.
+	fn saturating_from(t: T) -> Self;
+}
+
+/// Just like `Into` except that values out of the range of `T` saturate to its closest bound.
+///
+/// This is implemented for every type that `T` implements [`SaturatingFrom`] for.
+pub trait SaturatingInto<T: Sized>: Sized {
+	/// Consume self to return the closest value of `T`.
+	fn saturating_into(self) -> T;
+}
+
+impl<T: Sized, S: SaturatingFrom<T>> SaturatingInto<S> for T {
+	fn saturating_into(self) -> S {
+		S::saturating_from(self)
+	}
+}
+
 /// Saturating arithmetic operations, returning maximum or minimum values instead of overflowing.
 pub trait Saturating {
 	/// Saturating addition. Compute `self + rhs`, saturating at the numeric bounds instead of
@@ -300,3 +324,93 @@ pub trait SaturatedConversion {
 	}
 }
 impl<T: Sized> SaturatedConversion for T {}
+
+/// Multiply by a rational number `n / d` without intermediate overflow.
+pub trait MultiplyRational: Sized {
+	/// Returns `self * n / d`, rounded according to `r`.
+	///
+	/// Returns `None` if `d` is zero or the result does not fit in `Self`.
+	fn multiply_rational(self, n: Self, d: Self, r: Rounding) -> Option<Self>;
+}
+
+/// Round the quotient `result` of a division by `d`, given its `remainder`, according to `r`.
+///
+/// Returns `None` if rounding up overflows.
+fn round_div<T: BaseArithmetic + Copy>(result: T, remainder: T, d: T, r: Rounding) -> Option<T> {
+	let two = T::from(2);
+	if match r {
+		Rounding::Up => !remainder.is_zero(),
+		Rounding::Nearest => remainder > d / two,
+		Rounding::ToEven =>
+			remainder > d / two || (remainder == d - remainder && result % two == T::one()),
+		Rounding::Down => false,
+	} {
+		result.checked_add(&T::one())
+	} else {
+		Some(result)
+	}
+}
+
+macro_rules! impl_multiply_rational {
+	($($t:ty, $upper:ty);+ $(;)?) => {
+		$(impl MultiplyRational for $t {
+			fn multiply_rational(self, n: Self, d: Self, r: Rounding) -> Option<Self> {
+				if d == 0 {
+					return None
+				}
+				// `$upper` is twice as wide as `$t`, so the product always fits.
+				let sn = self as $upper * n as $upper;
+				let d = d as $upper;
+				round_div(sn / d, sn % d, d, r)?.try_into().ok()
+			}
+		})+
+	};
+}
+
+impl_multiply_rational!(u8, u16; u16, u32; u32, u64; u64, u128);
+
+impl MultiplyRational for u128 {
+	fn multiply_rational(self, n: Self, d: Self, r: Rounding) -> Option<Self> {
+		if d == 0 {
+			return None
+		}
+		// There is no wider primitive, so go through a 256-bit intermediate.
+		crate::helpers_128bit::multiply_by_rational_with_rounding(self, n, d, r)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn multiply_rational_works() {
+		assert_eq!(200u8.multiply_rational(200, 250, Rounding::Down), Some(160));
+		assert_eq!(200u8.multiply_rational(2, 1, Rounding::Down), None);
+		assert_eq!(7u8.multiply_rational(1, 0, Rounding::Down), None);
+		assert_eq!(u8::MAX.multiply_rational(u8::MAX, u8::MAX, Rounding::Up), Some(u8::MAX));
+
+		// 10 * 10 / 3 == 33.33...
+		assert_eq!(10u16.multiply_rational(10, 3, Rounding::Down), Some(33));
+		assert_eq!(10u32.multiply_rational(10, 3, Rounding::Up), Some(34));
+		assert_eq!(10u64.multiply_rational(10, 3, Rounding::Nearest), Some(33));
+		// 10 * 10 / 6 == 16.66...
+		assert_eq!(10u128.multiply_rational(10, 6, Rounding::Nearest), Some(17));
+		// An exact half rounds down.
+		assert_eq!(5u32.multiply_rational(1, 2, Rounding::Nearest), Some(2));
+		// Unless rounding to even.
+		assert_eq!(5u16.multiply_rational(1, 2, Rounding::ToEven), Some(2));
+		assert_eq!(7u64.multiply_rational(1, 2, Rounding::ToEven), Some(4));
+		assert_eq!(10u8.multiply_rational(10, 6, Rounding::ToEven), Some(17));
+		assert_eq!(u128::MAX.multiply_rational(1, 2, Rounding::ToEven), Some(u128::MAX / 2 + 1));
+
+		assert_eq!(u64::MAX.multiply_rational(u64::MAX, u64::MAX, Rounding::Up), Some(u64::MAX));
+		assert_eq!(u64::MAX.multiply_rational(3, 2, Rounding::Down), None);
+		assert_eq!(
+			u128::MAX.multiply_rational(u128::MAX - 1, u128::MAX, Rounding::Down),
+			Some(u128::MAX - 1)
+		);
+		assert_eq!(u128::MAX.multiply_rational(3, 2, Rounding::Up), None);
+		assert_eq!(1u128.multiply_rational(1, 0, Rounding::Up), None);
+	}
+}
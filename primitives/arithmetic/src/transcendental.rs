@@ -0,0 +1,257 @@
+// This file is part of Substrate.
+
+// Copyright (C) 2022 Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Transcendental functions (`exp`, `ln` and fractional `pow`) on fixed point numbers.
+//!
+//! Everything is computed with integer arithmetic on the inner values, so the results are
+//! deterministic across platforms, and all functions are `const`. Arguments are first reduced by
+//! powers of two, after which a short series converges to the full 18 decimals of precision:
+//!
+//! - `exp(x) = 2^k * exp(r)` with `r = x - k * ln(2)` in `[0, ln(2))`, using the Taylor series of
+//!   `exp(r)`.
+//! - `ln(x) = k * ln(2) + ln(m)` with `m = x / 2^k` in `[1, 2)`, using the series `ln(m) = 2 *
+//!   atanh((m - 1) / (m + 1))`.
+//!
+//! Intermediate values carry 9 more decimals than the inner values, and results are rounded to
+//! the nearest. The error bounds are documented on each function.
+
+use crate::{helpers_128bit::multiply_by_rational_with_rounding, FixedI128, FixedU128, Rounding};
+
+/// The inner value of one, for both `FixedU128` and `FixedI128`.
+const DIV: u128 = 1_000_000_000_000_000_000;
+
+/// Extra precision used for intermediate values, so that rounding errors of the series do not
+/// show in the result.
+const EXTRA: u128 = 1_000_000_000;
+
+/// The internal representation of one.
+const ONE: u128 = DIV * EXTRA;
+
+/// `ln(2)`, scaled by `ONE` and rounded down.
+const LN_2: u128 = 693_147_180_559_945_309_417_232_121;
+
+/// Bound on the magnitude of `exp` arguments, scaled by `ONE`. `e^48` overflows a `FixedU128` and
+/// `e^-48` rounds to zero.
+const EXP_BOUND: u128 = 48 * ONE;
+
+/// Returns `a * b / c`, rounded according to `r`. Only used where the result fits in a `u128`.
+const fn mul_div(a: u128, b: u128, c: u128, r: Rounding) -> u128 {
+	match multiply_by_rational_with_rounding(a, b, c, r) {
+		Some(v) => v,
+		None => u128::MAX,
+	}
+}
+
+/// Returns `a * b` for values scaled by `ONE`, rounded down.
+const fn mul(a: u128, b: u128) -> u128 {
+	mul_div(a, b, ONE, Rounding::Down)
+}
+
+/// Returns the inner value of `e^x`, where `x` is given by its sign and its magnitude scaled by
+/// `ONE`.
+///
+/// Returns `None` if the result overflows a `FixedU128`.
+const fn exp_inner(negative: bool, x: u128) -> Option<u128> {
+	if x >= EXP_BOUND {
+		return if negative { Some(0) } else { None }
+	}
+
+	// e^x = 2^k * e^r, where `r < ln(2)`.
+	let k = x / LN_2;
+	let r = x - k * LN_2;
+
+	// Taylor series: e^r = sum(r^n / n!). With `r < ln(2)` each term is less than `ln(2)` times
+	// the previous one, so this converges in about 25 terms.
+	let mut sum = ONE;
+	let mut term = ONE;
+	let mut n = 1;
+	while term > 0 {
+		term = mul(term, r) / n;
+		sum += term;
+		n += 1;
+	}
+
+	// `k` is below 70 here, so `2^k` and `EXTRA * 2^k` fit.
+	if !negative {
+		multiply_by_rational_with_rounding(sum, 1 << k, EXTRA, Rounding::Nearest)
+	} else {
+		// e^-x = e^-r / 2^k, where `e^-r = 1 / sum` is at most one.
+		let inverse = mul_div(ONE, ONE, sum, Rounding::Down);
+		multiply_by_rational_with_rounding(inverse, 1, EXTRA << k, Rounding::Nearest)
+	}
+}
+
+/// Returns `ln(x)` as a sign and a magnitude scaled by `ONE`, where `x` is an inner value.
+///
+/// `x` must not be zero.
+const fn ln_inner(x: u128) -> (bool, u128) {
+	// x = 2^e * m, where `1 <= m < 2`. The first guess for `e` gives `m` as many bits as `DIV`,
+	// which can still be below `DIV`.
+	let mut e = (127 - x.leading_zeros()) as i32 - (127 - DIV.leading_zeros()) as i32;
+	if (e >= 0 && x >> e < DIV) || (e < 0 && x << -e < DIV) {
+		e -= 1;
+	}
+	let m = if e >= 0 { mul_div(x, EXTRA, 1 << e, Rounding::Down) } else { (x << -e) * EXTRA };
+
+	// ln(m) = 2 * atanh(z) = 2 * sum(z^(2n + 1) / (2n + 1)), where `z = (m - 1) / (m + 1)` is
+	// less than 1/3.
+	let z = mul_div(m - ONE, ONE, m + ONE, Rounding::Down);
+	let z2 = mul(z, z);
+	let mut sum = z;
+	let mut power = z;
+	let mut n = 1;
+	while power > 0 {
+		power = mul(power, z2);
+		sum += power / (2 * n + 1);
+		n += 1;
+	}
+	let ln_m = 2 * sum;
+
+	// ln(x) = e * ln(2) + ln(m), which is negative for any negative `e` since `ln(m) < ln(2)`.
+	let e_ln_2 = e.unsigned_abs() as u128 * LN_2;
+	if e >= 0 {
+		(false, e_ln_2 + ln_m)
+	} else {
+		(true, e_ln_2 - ln_m)
+	}
+}
+
+/// Returns `e^x`.
+///
+/// The result is within a relative error of `10^-17` of the exact value, or within one unit of
+/// the last place for results below one. Returns `None` if the result does not fit in a
+/// `FixedU128`, i.e. for `x` above about `47.27`.
+pub const fn exp(x: FixedI128) -> Option<FixedU128> {
+	let x = x.into_inner();
+	match exp_inner(x < 0, x.unsigned_abs().saturating_mul(EXTRA)) {
+		Some(v) => Some(FixedU128::from_inner(v)),
+		None => None,
+	}
+}
+
+/// Returns the natural logarithm of `x`.
+///
+/// The result is within one unit of the last place of the exact value. Returns `None` if `x` is
+/// zero.
+pub const fn ln(x: FixedU128) -> Option<FixedI128> {
+	let x = x.into_inner();
+	if x == 0 {
+		return None
+	}
+	// The magnitude is at most `ln(u128::MAX)` scaled by `DIV`, which fits in an `i128`.
+	let (negative, v) = ln_inner(x);
+	let v = ((v + EXTRA / 2) / EXTRA) as i128;
+	Some(FixedI128::from_inner(if negative { -v } else { v }))
+}
+
+/// Returns `base^exponent`, computed as `e^(exponent * ln(base))`.
+///
+/// The error of `ln(base)` is scaled by `exponent`, so the relative error of the result grows
+/// with `exponent`, but stays within `10^-16` for exponents up to about `1000`. `0^0` is one.
+/// Returns `None` if the result does not fit in a `FixedU128`.
+pub const fn pow(base: FixedU128, exponent: FixedU128) -> Option<FixedU128> {
+	let (base, exponent) = (base.into_inner(), exponent.into_inner());
+	if exponent == 0 {
+		return Some(FixedU128::from_inner(DIV))
+	}
+	if base == 0 {
+		return Some(FixedU128::from_inner(0))
+	}
+	// An overflowing product saturates, which `exp_inner` maps to zero or `None`.
+	let (negative, ln_base) = ln_inner(base);
+	match exp_inner(negative, mul_div(ln_base, exponent, DIV, Rounding::Down)) {
+		Some(v) => Some(FixedU128::from_inner(v)),
+		None => None,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn fixed_u(x: f64) -> FixedU128 {
+		FixedU128::from_float(x)
+	}
+
+	fn fixed_i(x: f64) -> FixedI128 {
+		FixedI128::from_float(x)
+	}
+
+	fn assert_close(actual: f64, expected: f64) {
+		let tolerance = (expected.abs() * 1e-12).max(1e-15);
+		assert!((actual - expected).abs() <= tolerance, "{} is not close to {}", actual, expected);
+	}
+
+	#[test]
+	fn exp_works() {
+		assert_eq!(exp(FixedI128::from_inner(0)), Some(FixedU128::from_inner(DIV)));
+		assert_eq!(exp(fixed_i(1.0)).unwrap().into_inner(), 2_718_281_828_459_045_235);
+		for x in [-40.0, -10.0, -1.5, -0.5, 0.001, 0.5, 1.0, 2.5, 10.0, 30.0, 47.0] {
+			assert_close(exp(fixed_i(x)).unwrap().to_float(), x.exp());
+		}
+		assert!(exp(fixed_i(47.2)).is_some());
+		assert_eq!(exp(fixed_i(47.3)), None);
+		assert_eq!(exp(fixed_i(-100.0)), Some(FixedU128::from_inner(0)));
+		assert_eq!(exp(FixedI128::from_inner(i128::MIN)), Some(FixedU128::from_inner(0)));
+		assert_eq!(exp(FixedI128::from_inner(i128::MAX)), None);
+	}
+
+	#[test]
+	fn ln_works() {
+		assert_eq!(ln(FixedU128::from_inner(0)), None);
+		assert_eq!(ln(FixedU128::from_inner(DIV)), Some(FixedI128::from_inner(0)));
+		assert_eq!(ln(fixed_u(2.0)).unwrap().into_inner(), 693_147_180_559_945_309);
+		for x in [1e-18, 1e-9, 0.1, 0.5, 0.999, 1.001, 2.718281828459045, 10.0, 1e9, 3.4e20] {
+			assert_close(ln(fixed_u(x)).unwrap().to_float(), x.ln());
+		}
+		assert_close(
+			ln(FixedU128::from_inner(u128::MAX)).unwrap().to_float(),
+			(u128::MAX as f64 / 1e18).ln(),
+		);
+	}
+
+	#[test]
+	fn exp_and_ln_round_trip() {
+		for x in [1e-6, 0.3, 1.0, 7.5, 12345.678, 1e15] {
+			let x = fixed_u(x);
+			let round_trip = exp(ln(x).unwrap()).unwrap();
+			assert_close(round_trip.to_float(), x.to_float());
+		}
+	}
+
+	#[test]
+	fn pow_works() {
+		assert_eq!(pow(fixed_u(0.0), fixed_u(0.0)), Some(fixed_u(1.0)));
+		assert_eq!(pow(fixed_u(0.0), fixed_u(2.5)), Some(fixed_u(0.0)));
+		assert_eq!(pow(fixed_u(5.0), fixed_u(0.0)), Some(fixed_u(1.0)));
+		assert_close(pow(fixed_u(2.0), fixed_u(10.0)).unwrap().to_float(), 1024.0);
+		assert_close(pow(fixed_u(4.0), fixed_u(0.5)).unwrap().to_float(), 2.0);
+		assert_close(pow(fixed_u(0.5), fixed_u(3.0)).unwrap().to_float(), 0.125);
+		assert_close(pow(fixed_u(1.05), fixed_u(12.5)).unwrap().to_float(), 1.05f64.powf(12.5));
+		assert_close(pow(fixed_u(0.999), fixed_u(1000.0)).unwrap().to_float(), 0.999f64.powi(1000));
+		assert_eq!(pow(fixed_u(2.0), fixed_u(70.0)), None);
+		assert_eq!(pow(fixed_u(1e-9), fixed_u(1e9)), Some(fixed_u(0.0)));
+	}
+
+	#[test]
+	fn can_be_used_in_const_context() {
+		const E: Option<FixedU128> = exp(FixedI128::from_inner(1_000_000_000_000_000_000));
+		const LN_E: Option<FixedI128> = ln(FixedU128::from_inner(2_718_281_828_459_045_235));
+		assert_eq!(E, Some(FixedU128::from_inner(2_718_281_828_459_045_235)));
+		assert_close(LN_E.unwrap().to_float(), 1.0);
+	}
+}
@@ -19,7 +19,7 @@
 
 use serde::{Deserialize, Serialize};
 
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 
 /// Container for all related spans and events for the block being traced.
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -44,6 +44,335 @@ pub struct BlockTrace {
 	pub events: Vec<Event>,
 }
 
+impl BlockTrace {
+	/// Extract the span with `root_id` and all of its transitive descendants, along with their
+	/// events, as a standalone trace. The root of the new trace has no parent.
+	///
+	/// Returns `None` if there is no span with `root_id`.
+	pub fn subtree(&self, root_id: u64) -> Option<BlockTrace> {
+		if !self.spans.iter().any(|s| s.id == root_id) {
+			return None
+		}
+		let ids = self.descendants_of([root_id]);
+		let spans = self
+			.spans
+			.iter()
+			.filter(|s| ids.contains(&s.id))
+			.cloned()
+			.map(|mut s| {
+				if s.id == root_id {
+					s.parent_id = None;
+				}
+				s
+			})
+			.collect();
+		let events = self
+			.events
+			.iter()
+			.filter(|e| e.parent_id.map_or(false, |id| ids.contains(&id)))
+			.cloned()
+			.collect();
+		Some(BlockTrace { spans, events, ..self.clone_header() })
+	}
+
+	/// Group the events by the id of their parent span, keeping their original order.
+	///
+	/// Events without a parent are grouped under `None`.
+	pub fn events_by_parent(&self) -> FxHashMap<Option<u64>, Vec<&Event>> {
+		let mut groups: FxHashMap<Option<u64>, Vec<&Event>> = FxHashMap::default();
+		for event in &self.events {
+			groups.entry(event.parent_id).or_default().push(event);
+		}
+		groups
+	}
+
+	/// Collapse spans nested deeper than `max_depth` into their ancestor at `max_depth`, where root
+	/// spans are at depth zero. The deep spans are dropped and their events are reparented to
+	/// that ancestor.
+	pub fn collapse_below_depth(&self, max_depth: usize) -> BlockTrace {
+		let parents: FxHashMap<u64, Option<u64>> =
+			self.spans.iter().map(|s| (s.id, s.parent_id)).collect();
+		// The span that `id` collapses into, which is `id` itself if it isn't too deep.
+		let collapse_into = |id: u64| {
+			// `id` followed by its ancestors, up to the root.
+			let mut ancestry = vec![id];
+			let mut current = id;
+			while let Some(Some(parent_id)) = parents.get(&current) {
+				if !parents.contains_key(parent_id) || ancestry.contains(parent_id) {
+					break
+				}
+				ancestry.push(*parent_id);
+				current = *parent_id;
+			}
+			ancestry[(ancestry.len() - 1).saturating_sub(max_depth)]
+		};
+
+		let mut trace = self.clone();
+		for span in self.spans.iter().filter(|s| collapse_into(s.id) != s.id) {
+			trace.remove_span(span.id);
+		}
+		trace
+	}
+
+	/// Clear every span and event `parent_id` that refers to a span missing from the trace, making
+	/// them roots. Returns the number of references cleared.
+	pub fn repair_parents(&mut self) -> usize {
+		let ids: FxHashSet<u64> = self.spans.iter().map(|s| s.id).collect();
+		let spans = self.spans.iter_mut().map(|s| &mut s.parent_id);
+		let mut repaired = 0;
+		for reference in spans.chain(self.events.iter_mut().map(|e| &mut e.parent_id)) {
+			if reference.map_or(false, |id| !ids.contains(&id)) {
+				*reference = None;
+				repaired += 1;
+			}
+		}
+		repaired
+	}
+
+	/// Remove the span with `span_id`, moving its child spans and events up to its parent so
+	/// that no references to the removed span are left behind.
+	///
+	/// Does nothing if there is no span with `span_id`.
+	pub fn remove_span(&mut self, span_id: u64) {
+		let index = match self.spans.iter().position(|s| s.id == span_id) {
+			Some(index) => index,
+			None => return,
+		};
+		// A span that is its own parent leaves its children without one.
+		let parent_id = self.spans.remove(index).parent_id.filter(|id| *id != span_id);
+		let spans = self.spans.iter_mut().map(|s| &mut s.parent_id);
+		for reference in spans.chain(self.events.iter_mut().map(|e| &mut e.parent_id)) {
+			if *reference == Some(span_id) {
+				*reference = parent_id;
+			}
+		}
+	}
+
+	/// Render the spans as an indented call tree, one `name@target (n events)` line per span.
+	///
+	/// Spans whose parent is missing from the trace are printed as roots, as are spans that are
+	/// only reachable through a cycle of parent ids.
+	pub fn to_tree_string(&self) -> String {
+		let ids: FxHashSet<u64> = self.spans.iter().map(|s| s.id).collect();
+		let mut roots = Vec::new();
+		let mut children: FxHashMap<u64, Vec<&Span>> = FxHashMap::default();
+		for span in &self.spans {
+			match span.parent_id.filter(|id| ids.contains(id)) {
+				Some(parent_id) => children.entry(parent_id).or_default().push(span),
+				None => roots.push(span),
+			}
+		}
+		let mut event_counts: FxHashMap<u64, usize> = FxHashMap::default();
+		for parent_id in self.events.iter().filter_map(|e| e.parent_id) {
+			*event_counts.entry(parent_id).or_default() += 1;
+		}
+
+		let mut out = String::new();
+		let mut visited = FxHashSet::default();
+		for root in roots.into_iter().chain(self.spans.iter()) {
+			let mut stack = vec![(root, 0)];
+			while let Some((span, depth)) = stack.pop() {
+				if !visited.insert(span.id) {
+					continue
+				}
+				out.push_str(&format!(
+					"{}{}@{} ({} events)\n",
+					"  ".repeat(depth),
+					span.name,
+					span.target,
+					event_counts.get(&span.id).copied().unwrap_or_default(),
+				));
+				if let Some(children) = children.get(&span.id) {
+					stack.extend(children.iter().rev().map(|child| (*child, depth + 1)));
+				}
+			}
+		}
+		out
+	}
+
+	/// A copy of this trace keeping only the spans whose name starts with `prefix`, along with all
+	/// of their transitive descendants and their events.
+	///
+	/// Spans whose parent was dropped become roots of the new trace.
+	pub fn focus_on_name(&self, prefix: &str) -> BlockTrace {
+		let ids = self
+			.descendants_of(self.spans.iter().filter(|s| s.name.starts_with(prefix)).map(|s| s.id));
+		let spans = self
+			.spans
+			.iter()
+			.filter(|s| ids.contains(&s.id))
+			.cloned()
+			.map(|mut s| {
+				if s.parent_id.map_or(false, |id| !ids.contains(&id)) {
+					s.parent_id = None;
+				}
+				s
+			})
+			.collect();
+		let events = self
+			.events
+			.iter()
+			.filter(|e| e.parent_id.map_or(false, |id| ids.contains(&id)))
+			.cloned()
+			.collect();
+		BlockTrace { spans, events, ..self.clone_header() }
+	}
+
+	/// A copy of this trace keeping a uniformly random sample of `k` events, in their original
+	/// order. Spans are kept intact.
+	///
+	/// The sample is drawn with reservoir sampling, using hashes of `seed` as the source of
+	/// randomness, so the same seed always picks the same events.
+	pub fn sample_events(&self, k: usize, seed: u64) -> BlockTrace {
+		if k >= self.events.len() {
+			return self.clone()
+		}
+		let mut reservoir = (0..k).collect::<Vec<_>>();
+		for i in k..self.events.len() {
+			let mut input = [0u8; 16];
+			input[..8].copy_from_slice(&seed.to_le_bytes());
+			input[8..].copy_from_slice(&(i as u64).to_le_bytes());
+			let j = u64::from_le_bytes(sp_core::hashing::twox_64(&input)) % (i as u64 + 1);
+			if let Some(slot) = reservoir.get_mut(j as usize) {
+				*slot = i;
+			}
+		}
+		reservoir.sort_unstable();
+		let events = reservoir.into_iter().map(|i| self.events[i].clone()).collect();
+		BlockTrace { spans: self.spans.clone(), events, ..self.clone_header() }
+	}
+
+	/// A copy of this trace keeping only the events at `min_level` or more severe, where
+	/// `ERROR > WARN > INFO > DEBUG > TRACE`.
+	///
+	/// Levels are matched case-insensitively. Events without a level, or with a level that is not
+	/// recognised, are always kept. An unrecognised `min_level` keeps every event.
+	pub fn filter_by_level(&self, min_level: &str) -> BlockTrace {
+		let min_rank = level_rank(min_level).unwrap_or_default();
+		let events = self
+			.events
+			.iter()
+			.filter(|e| {
+				e.level.as_deref().and_then(level_rank).map_or(true, |rank| rank >= min_rank)
+			})
+			.cloned()
+			.collect();
+		BlockTrace { spans: self.spans.clone(), events, ..self.clone_header() }
+	}
+
+	/// Write this trace as JSON to `w`.
+	///
+	/// Spans and events are serialized straight into the writer, so unlike
+	/// `serde_json::to_string` the whole payload is never held in memory at once.
+	pub fn write_json<W: std::io::Write>(&self, w: &mut W) -> std::io::Result<()> {
+		serde_json::to_writer(w, self).map_err(Into::into)
+	}
+
+	/// Serialize this trace to a JSON string, pretty-printed if `pretty` is set.
+	pub fn to_json(&self, pretty: bool) -> String {
+		let json =
+			if pretty { serde_json::to_string_pretty(self) } else { serde_json::to_string(self) };
+		json.expect("BlockTrace only contains strings, integers and string maps; qed")
+	}
+
+	/// Length in bytes of the JSON [`Self::to_json`] would return, without building it.
+	pub fn json_len(&self, pretty: bool) -> usize {
+		let mut counter = ByteCounter(0);
+		let written = if pretty {
+			serde_json::to_writer_pretty(&mut counter, self)
+		} else {
+			serde_json::to_writer(&mut counter, self)
+		};
+		written.expect("BlockTrace only contains strings, integers and string maps; qed");
+		counter.0
+	}
+
+	/// The tracing targets of this trace, parsed from the comma separated `tracing_targets`.
+	///
+	/// Targets are trimmed, and empty or repeated ones are dropped.
+	pub fn targets(&self) -> Vec<String> {
+		let mut seen = FxHashSet::default();
+		self.tracing_targets
+			.split(',')
+			.map(str::trim)
+			.filter(|t| !t.is_empty() && seen.insert(*t))
+			.map(str::to_owned)
+			.collect()
+	}
+
+	/// Set `tracing_targets` to the canonical comma separated form of `targets`, as it would be
+	/// parsed by [`Self::targets`].
+	pub fn set_targets(&mut self, targets: &[&str]) {
+		self.tracing_targets = targets.join(",");
+		self.tracing_targets = self.targets().join(",");
+	}
+
+	/// Release unused capacity held by the spans, events and event data of this trace.
+	///
+	/// Useful after filtering a trace that is kept around for a long time.
+	pub fn shrink_to_fit(&mut self) {
+		self.spans.shrink_to_fit();
+		self.events.shrink_to_fit();
+		for event in &mut self.events {
+			event.data.string_values.shrink_to_fit();
+		}
+	}
+
+	/// Ids of the spans in `roots` and all of their transitive descendants.
+	fn descendants_of(&self, roots: impl IntoIterator<Item = u64>) -> FxHashSet<u64> {
+		let mut children: FxHashMap<u64, Vec<u64>> = FxHashMap::default();
+		for span in &self.spans {
+			if let Some(parent_id) = span.parent_id {
+				children.entry(parent_id).or_default().push(span.id);
+			}
+		}
+		let mut ids = FxHashSet::default();
+		let mut stack = roots.into_iter().collect::<Vec<_>>();
+		while let Some(id) = stack.pop() {
+			// A span can only be visited once, so cycles can't loop forever.
+			if ids.insert(id) {
+				stack.extend(children.get(&id).into_iter().flatten());
+			}
+		}
+		ids
+	}
+
+	/// A copy of this trace's metadata with no spans or events.
+	fn clone_header(&self) -> BlockTrace {
+		BlockTrace {
+			block_hash: self.block_hash.clone(),
+			parent_hash: self.parent_hash.clone(),
+			tracing_targets: self.tracing_targets.clone(),
+			storage_keys: self.storage_keys.clone(),
+			methods: self.methods.clone(),
+			spans: Vec::new(),
+			events: Vec::new(),
+		}
+	}
+}
+
+/// A writer that discards its input, only counting the bytes written to it.
+struct ByteCounter(usize);
+
+impl std::io::Write for ByteCounter {
+	fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+		self.0 += buf.len();
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		Ok(())
+	}
+}
+
+/// Severity of a tracing level, higher being more severe.
+fn level_rank(level: &str) -> Option<u8> {
+	["TRACE", "DEBUG", "INFO", "WARN", "ERROR"]
+		.iter()
+		.position(|l| l.eq_ignore_ascii_case(level))
+		.map(|rank| rank as u8)
+}
+
 /// Represents a tracing event, complete with recorded data.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
@@ -54,6 +383,9 @@ pub struct Event {
 	pub data: Data,
 	/// Parent id, if it exists
 	pub parent_id: Option<u64>,
+	/// Level of the event (`ERROR`, `WARN`, `INFO`, `DEBUG` or `TRACE`), if known
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub level: Option<String>,
 }
 
 /// Represents a single instance of a tracing span.
@@ -99,3 +431,318 @@ pub enum TraceBlockResponse {
 	/// Successful block tracing response
 	BlockTrace(BlockTrace),
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn span(id: u64, parent_id: Option<u64>) -> Span {
+		Span { id, parent_id, name: format!("span_{}", id), target: "test".to_owned(), wasm: false }
+	}
+
+	fn event(parent_id: Option<u64>) -> Event {
+		Event { target: "test".to_owned(), data: Default::default(), parent_id, level: None }
+	}
+
+	fn trace(spans: Vec<Span>, events: Vec<Event>) -> BlockTrace {
+		BlockTrace {
+			block_hash: "0x01".to_owned(),
+			parent_hash: "0x00".to_owned(),
+			tracing_targets: "pallet,frame,state".to_owned(),
+			storage_keys: String::new(),
+			methods: String::new(),
+			spans,
+			events,
+		}
+	}
+
+	fn span_ids(trace: &BlockTrace) -> Vec<u64> {
+		trace.spans.iter().map(|s| s.id).collect()
+	}
+
+	#[test]
+	fn subtree_works() {
+		let trace = trace(
+			vec![
+				span(1, None),
+				span(2, Some(1)),
+				span(3, Some(2)),
+				span(4, Some(3)),
+				span(5, Some(1)),
+			],
+			vec![event(Some(1)), event(Some(2)), event(Some(4)), event(Some(5)), event(None)],
+		);
+
+		let sub = trace.subtree(2).unwrap();
+		assert_eq!(span_ids(&sub), vec![2, 3, 4]);
+		assert_eq!(sub.spans[0].parent_id, None);
+		assert_eq!(sub.spans[1].parent_id, Some(2));
+		assert_eq!(
+			sub.events.iter().map(|e| e.parent_id).collect::<Vec<_>>(),
+			vec![Some(2), Some(4)]
+		);
+		assert_eq!(sub.block_hash, trace.block_hash);
+
+		assert!(trace.subtree(42).is_none());
+	}
+
+	#[test]
+	fn events_by_parent_works() {
+		let mut events = vec![event(Some(1)), event(Some(2)), event(None), event(Some(1))];
+		for (i, e) in events.iter_mut().enumerate() {
+			e.data.string_values.insert("index".to_owned(), i.to_string());
+		}
+		let trace = trace(vec![span(1, None), span(2, Some(1))], events);
+
+		let groups = trace.events_by_parent();
+		let indices = |parent_id| {
+			groups[&parent_id]
+				.iter()
+				.map(|e| e.data.string_values["index"].clone())
+				.collect::<Vec<_>>()
+		};
+		assert_eq!(groups.len(), 3);
+		assert_eq!(indices(Some(1)), vec!["0", "3"]);
+		assert_eq!(indices(Some(2)), vec!["1"]);
+		assert_eq!(indices(None), vec!["2"]);
+	}
+
+	#[test]
+	fn collapse_below_depth_works() {
+		let trace = trace(
+			vec![
+				span(1, None),
+				span(2, Some(1)),
+				span(3, Some(2)),
+				span(4, Some(3)),
+				span(5, Some(4)),
+				span(6, Some(1)),
+			],
+			vec![event(Some(1)), event(Some(4)), event(Some(5)), event(Some(6)), event(None)],
+		);
+
+		let collapsed = trace.collapse_below_depth(2);
+		assert_eq!(span_ids(&collapsed), vec![1, 2, 3, 6]);
+		assert_eq!(
+			collapsed.events.iter().map(|e| e.parent_id).collect::<Vec<_>>(),
+			vec![Some(1), Some(3), Some(3), Some(6), None]
+		);
+
+		let collapsed = trace.collapse_below_depth(0);
+		assert_eq!(span_ids(&collapsed), vec![1]);
+		assert!(collapsed.events.iter().all(|e| e.parent_id.map_or(true, |id| id == 1)));
+	}
+
+	#[test]
+	fn to_tree_string_works() {
+		let nested = trace(
+			vec![span(1, None), span(2, Some(1)), span(3, Some(1)), span(4, Some(42))],
+			vec![event(Some(2)), event(Some(2)), event(Some(3))],
+		);
+		assert_eq!(
+			nested.to_tree_string(),
+			"span_1@test (0 events)\n  span_2@test (2 events)\n  span_3@test (1 events)\n\
+			 span_4@test (0 events)\n",
+		);
+
+		// a cycle of parents is printed once.
+		let cyclic = trace(vec![span(1, Some(2)), span(2, Some(1))], vec![]);
+		assert_eq!(cyclic.to_tree_string(), "span_1@test (0 events)\n  span_2@test (0 events)\n");
+	}
+
+	#[test]
+	fn shrink_to_fit_works() {
+		let mut trace = trace(Vec::with_capacity(64), Vec::with_capacity(64));
+		trace.spans.extend((0..64).map(|id| span(id, None)));
+		let mut e = event(None);
+		e.data.string_values.extend((0..64).map(|i| (i.to_string(), String::new())));
+		trace.events.push(e);
+
+		trace.spans.truncate(1);
+		trace.events[0].data.string_values.clear();
+		trace.shrink_to_fit();
+
+		assert!(trace.spans.capacity() < 64);
+		assert!(trace.events.capacity() < 64);
+		assert!(trace.events[0].data.string_values.capacity() < 64);
+		assert_eq!(span_ids(&trace), vec![0]);
+	}
+
+	#[test]
+	fn filter_by_level_works() {
+		let leveled =
+			|level: Option<&str>| Event { level: level.map(str::to_owned), ..event(None) };
+		let trace = trace(
+			vec![span(1, None)],
+			vec![
+				leveled(Some("ERROR")),
+				leveled(Some("INFO")),
+				leveled(Some("DEBUG")),
+				leveled(Some("TRACE")),
+				leveled(None),
+				leveled(Some("info")),
+			],
+		);
+		let levels = |t: &BlockTrace| t.events.iter().map(|e| e.level.clone()).collect::<Vec<_>>();
+
+		let info = trace.filter_by_level("INFO");
+		assert_eq!(
+			levels(&info),
+			vec![Some("ERROR".to_owned()), Some("INFO".to_owned()), None, Some("info".to_owned())]
+		);
+		assert_eq!(span_ids(&info), vec![1]);
+		assert_eq!(trace.filter_by_level("trace").events.len(), 6);
+		assert_eq!(trace.filter_by_level("ERROR").events.len(), 2);
+	}
+
+	#[test]
+	fn event_without_level_deserializes() {
+		let event: Event =
+			serde_json::from_str(r#"{"target":"test","data":{"stringValues":{}},"parentId":1}"#)
+				.unwrap();
+		assert_eq!(event.parent_id, Some(1));
+		assert_eq!(event.level, None);
+	}
+
+	#[test]
+	fn write_json_works() {
+		let trace = trace(vec![span(1, None), span(2, Some(1))], vec![event(Some(2))]);
+		let mut out = Vec::new();
+		trace.write_json(&mut out).unwrap();
+
+		let decoded: BlockTrace = serde_json::from_slice(&out).unwrap();
+		assert_eq!(decoded.block_hash, trace.block_hash);
+		assert_eq!(span_ids(&decoded), vec![1, 2]);
+		assert_eq!(decoded.events.len(), 1);
+		assert_eq!(decoded.events[0].parent_id, Some(2));
+		assert_eq!(out, serde_json::to_vec(&trace).unwrap());
+	}
+
+	#[test]
+	fn focus_on_name_works() {
+		let named =
+			|id, parent_id, name: &str| Span { name: name.to_owned(), ..span(id, parent_id) };
+		let trace = trace(
+			vec![
+				named(1, None, "execute_block"),
+				named(2, Some(1), "on_initialize"),
+				named(3, Some(2), "read"),
+				named(4, Some(3), "on_initialize_inner"),
+				named(5, Some(1), "apply_extrinsic"),
+				named(6, Some(5), "on_initialize"),
+				named(7, Some(5), "write"),
+			],
+			vec![event(Some(1)), event(Some(3)), event(Some(4)), event(Some(7)), event(None)],
+		);
+
+		let focused = trace.focus_on_name("on_initialize");
+		assert_eq!(span_ids(&focused), vec![2, 3, 4, 6]);
+		assert_eq!(
+			focused.spans.iter().map(|s| s.parent_id).collect::<Vec<_>>(),
+			vec![None, Some(2), Some(3), None]
+		);
+		assert_eq!(
+			focused.events.iter().map(|e| e.parent_id).collect::<Vec<_>>(),
+			vec![Some(3), Some(4)]
+		);
+		assert!(trace.focus_on_name("missing").spans.is_empty());
+	}
+
+	#[test]
+	fn sample_events_works() {
+		let trace = trace(vec![span(1, None)], (0..100).map(|i| event(Some(i))).collect());
+		let parents = |t: &BlockTrace| t.events.iter().map(|e| e.parent_id).collect::<Vec<_>>();
+
+		let sample = trace.sample_events(10, 42);
+		assert_eq!(sample.events.len(), 10);
+		assert_eq!(span_ids(&sample), vec![1]);
+		assert_eq!(parents(&sample), parents(&trace.sample_events(10, 42)));
+		assert_ne!(parents(&sample), parents(&trace.sample_events(10, 43)));
+		// Original order is preserved.
+		assert!(parents(&sample).windows(2).all(|w| w[0] < w[1]));
+
+		assert_eq!(trace.sample_events(0, 42).events.len(), 0);
+		assert_eq!(parents(&trace.sample_events(100, 42)), parents(&trace));
+		assert_eq!(trace.sample_events(1000, 42).events.len(), 100);
+	}
+
+	#[test]
+	fn remove_span_works() {
+		let mut trace = trace(
+			vec![span(1, None), span(2, Some(1)), span(3, Some(2)), span(4, Some(2))],
+			vec![event(Some(2)), event(Some(3)), event(None)],
+		);
+
+		trace.remove_span(2);
+		assert_eq!(span_ids(&trace), vec![1, 3, 4]);
+		assert_eq!(
+			trace.spans.iter().map(|s| s.parent_id).collect::<Vec<_>>(),
+			vec![None, Some(1), Some(1)]
+		);
+		assert_eq!(
+			trace.events.iter().map(|e| e.parent_id).collect::<Vec<_>>(),
+			vec![Some(1), Some(3), None]
+		);
+
+		trace.remove_span(1);
+		assert_eq!(trace.spans.iter().map(|s| s.parent_id).collect::<Vec<_>>(), vec![None, None]);
+		assert_eq!(trace.events[0].parent_id, None);
+
+		// Removing a missing span is a no-op.
+		trace.remove_span(42);
+		assert_eq!(span_ids(&trace), vec![3, 4]);
+	}
+
+	#[test]
+	fn repair_parents_works() {
+		let mut trace = trace(
+			vec![span(1, None), span(2, Some(1)), span(3, Some(9))],
+			vec![event(Some(2)), event(Some(8)), event(None)],
+		);
+
+		assert_eq!(trace.repair_parents(), 2);
+		assert_eq!(
+			trace.spans.iter().map(|s| s.parent_id).collect::<Vec<_>>(),
+			vec![None, Some(1), None]
+		);
+		assert_eq!(
+			trace.events.iter().map(|e| e.parent_id).collect::<Vec<_>>(),
+			vec![Some(2), None, None]
+		);
+		assert_eq!(trace.repair_parents(), 0);
+	}
+
+	#[test]
+	fn to_json_works() {
+		let mut e = event(Some(2));
+		e.data.string_values.insert("key".to_owned(), "value".to_owned());
+		let trace = trace(vec![span(1, None), span(2, Some(1))], vec![e]);
+
+		let compact = trace.to_json(false);
+		let pretty = trace.to_json(true);
+		assert!(pretty.len() > compact.len());
+		assert_eq!(compact, serde_json::to_string(&trace).unwrap());
+		assert_eq!(trace.json_len(false), compact.len());
+		assert_eq!(trace.json_len(true), pretty.len());
+
+		let decoded: BlockTrace = serde_json::from_str(&pretty).unwrap();
+		assert_eq!(span_ids(&decoded), vec![1, 2]);
+	}
+
+	#[test]
+	fn targets_works() {
+		let mut trace = trace(vec![], vec![]);
+		assert_eq!(trace.targets(), vec!["pallet", "frame", "state"]);
+
+		trace.tracing_targets = " a, ,b,a ".to_owned();
+		assert_eq!(trace.targets(), vec!["a", "b"]);
+
+		trace.set_targets(&[" a", "", "b ", "a"]);
+		assert_eq!(trace.tracing_targets, "a,b");
+		assert_eq!(trace.targets(), vec!["a", "b"]);
+
+		trace.set_targets(&[]);
+		assert_eq!(trace.tracing_targets, "");
+		assert!(trace.targets().is_empty());
+	}
+}